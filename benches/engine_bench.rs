@@ -0,0 +1,236 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use ogame_battle_sim::{
+    BattleRng, FastRng, Fleet, Player, TechLevels, UnitType, rapid_fire_for,
+    simulate_battle_with_rng, stats_for,
+};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::iter;
+use std::time::Duration;
+
+fn player(units: &[(UnitType, u64)], level: u8) -> Player {
+    Player {
+        fleet: Fleet {
+            units: units.iter().copied().collect::<HashMap<_, _>>(),
+        },
+        tech: TechLevels {
+            weapon: level,
+            shield: level,
+            armor: level,
+        },
+    }
+}
+
+/// A ship of the baseline engine, which kept one of these per unit.
+#[derive(Clone)]
+struct Unit {
+    hull: f64,
+    shield: f64,
+    unit_type: UnitType,
+}
+
+impl Unit {
+    fn new(unit_type: UnitType, tech: &TechLevels) -> Self {
+        Self {
+            hull: Self::hull_pts(unit_type, tech),
+            shield: Self::shield_pts(unit_type, tech),
+            unit_type,
+        }
+    }
+
+    fn hull_pts(ut: UnitType, tech: &TechLevels) -> f64 {
+        stats_for(ut).hull * (1.0 + 0.1 * tech.armor as f64) / 10.0
+    }
+
+    fn shield_pts(ut: UnitType, tech: &TechLevels) -> f64 {
+        stats_for(ut).base_shield * (1.0 + 0.1 * tech.shield as f64)
+    }
+
+    fn attack_pts(ut: UnitType, tech: &TechLevels) -> f64 {
+        stats_for(ut).base_attack * (1.0 + 0.1 * tech.weapon as f64)
+    }
+}
+
+fn units_map_to_array(map: &HashMap<UnitType, u64>) -> [u64; UnitType::count()] {
+    let mut arr = [0u64; UnitType::count()];
+    for (&ut, &cnt) in map {
+        arr[ut as usize] = cnt;
+    }
+    arr
+}
+
+/// The baseline engine, copied from the tree before the structure-of-arrays
+/// combat state: one `Unit` per ship, stats looked up per shot and per
+/// shield reset, and the two volleys written out separately. The only change
+/// is the early stop once a side is wiped out, which the current engine also
+/// does, so both run the same number of rounds.
+fn legacy_battle(
+    attacker: &Player,
+    defender: &Player,
+    rng: &mut FastRng,
+) -> ([u64; UnitType::count()], [u64; UnitType::count()]) {
+    let attacker_units = &attacker.fleet.units;
+    let defender_units = &defender.fleet.units;
+
+    let mut n_attacker_units = attacker_units.values().copied().sum::<u64>();
+    let mut n_defender_units = defender_units.values().copied().sum::<u64>();
+
+    let mut attackers = Vec::with_capacity(n_attacker_units as usize);
+    let mut defenders = Vec::with_capacity(n_defender_units as usize);
+    for (&ut, &count) in attacker_units {
+        let proto = Unit::new(ut, &attacker.tech);
+        attackers.extend(iter::repeat_n(proto, count as usize));
+    }
+    for (&ut, &count) in defender_units {
+        let proto = Unit::new(ut, &defender.tech);
+        defenders.extend(iter::repeat_n(proto, count as usize));
+    }
+    let mut attacker_units_tracker = units_map_to_array(attacker_units);
+    let mut defender_units_tracker = units_map_to_array(defender_units);
+
+    for _round in 0..6 {
+        if n_attacker_units == 0 || n_defender_units == 0 {
+            break;
+        }
+        let attacker_units_tracker_clone = attacker_units_tracker;
+        let defender_units_tracker_clone = defender_units_tracker;
+        let mut tmp_n_defender_units = n_defender_units;
+        let mut tmp_n_attacker_units = n_attacker_units;
+
+        for (unit_type_index, &count) in attacker_units_tracker_clone.iter().enumerate() {
+            let attacker_unit_type = UnitType::ALL[unit_type_index];
+            let dmg = Unit::attack_pts(attacker_unit_type, &attacker.tech);
+            for _ in 0..count {
+                let mut keep_firing = true;
+                while keep_firing && tmp_n_defender_units > 0 {
+                    let defender_index = rng.next(tmp_n_defender_units) as usize;
+                    let defender_unit = &mut defenders[defender_index];
+                    let defender_max_shield =
+                        Unit::shield_pts(defender_unit.unit_type, &defender.tech);
+                    if defender_unit.shield > 0.0 {
+                        let pct = dmg / defender_max_shield * 100.0;
+                        if pct <= 1.0 {
+                            break;
+                        }
+                        let overflow_damage = f64::max(0.0, dmg - defender_unit.shield);
+                        defender_unit.shield = f64::max(0.0, defender_unit.shield - dmg);
+                        defender_unit.hull -= overflow_damage;
+                    } else {
+                        defender_unit.hull -= dmg;
+                    }
+                    if defender_unit.hull <= 0.0 {
+                        defender_units_tracker[defender_unit.unit_type as usize] -= 1;
+                        defenders.swap(defender_index, (tmp_n_defender_units - 1) as usize);
+                        tmp_n_defender_units -= 1;
+                        break;
+                    }
+                    let max_hull = Unit::hull_pts(defender_unit.unit_type, &defender.tech);
+                    if defender_unit.hull < 0.7 * max_hull {
+                        let explode_chance = 1.0 - (defender_unit.hull / max_hull);
+                        if rng.next_f64() < explode_chance {
+                            defender_units_tracker[defender_unit.unit_type as usize] -= 1;
+                            defenders.swap(defender_index, (tmp_n_defender_units - 1) as usize);
+                            tmp_n_defender_units -= 1;
+                            break;
+                        }
+                    }
+
+                    let rf = rapid_fire_for(attacker_unit_type, defender_unit.unit_type);
+                    keep_firing = rf > 1 && rng.next_f32() < (rf as f32 - 1.0) / rf as f32;
+                }
+            }
+        }
+
+        for (unit_type_index, &count) in defender_units_tracker_clone.iter().enumerate() {
+            let defender_unit_type = UnitType::ALL[unit_type_index];
+            let dmg = Unit::attack_pts(defender_unit_type, &defender.tech);
+            for _ in 0..count {
+                let mut keep_firing = true;
+                while keep_firing && tmp_n_attacker_units > 0 {
+                    let atk_idx = rng.next(tmp_n_attacker_units) as usize;
+                    let au = &mut attackers[atk_idx];
+                    let max_shield = Unit::shield_pts(au.unit_type, &attacker.tech);
+                    if au.shield > 0.0 {
+                        let pct = dmg / max_shield * 100.0;
+                        if pct <= 1.0 {
+                            break;
+                        }
+                        let overflow = f64::max(0.0, dmg - au.shield);
+                        au.shield = f64::max(0.0, au.shield - dmg);
+                        au.hull -= overflow;
+                    } else {
+                        au.hull -= dmg;
+                    }
+                    if au.hull <= 0.0 {
+                        attacker_units_tracker[au.unit_type as usize] -= 1;
+                        attackers.swap(atk_idx, (tmp_n_attacker_units - 1) as usize);
+                        tmp_n_attacker_units -= 1;
+                        break;
+                    }
+                    let max_hull = Unit::hull_pts(au.unit_type, &attacker.tech);
+                    if au.hull < 0.7 * max_hull {
+                        let explode_chance = 1.0 - (au.hull / max_hull);
+                        if rng.next_f64() < explode_chance {
+                            attacker_units_tracker[au.unit_type as usize] -= 1;
+                            attackers.swap(atk_idx, (tmp_n_attacker_units - 1) as usize);
+                            tmp_n_attacker_units -= 1;
+                            break;
+                        }
+                    }
+
+                    let rf = rapid_fire_for(defender_unit_type, au.unit_type);
+                    keep_firing = rf > 1 && rng.next_f32() < (rf as f32 - 1.0) / rf as f32;
+                }
+            }
+        }
+
+        for unit in &mut defenders[..n_defender_units as usize] {
+            unit.shield = Unit::shield_pts(unit.unit_type, &defender.tech);
+        }
+        for unit in &mut attackers[..n_attacker_units as usize] {
+            unit.shield = Unit::shield_pts(unit.unit_type, &attacker.tech);
+        }
+        n_defender_units = tmp_n_defender_units;
+        n_attacker_units = tmp_n_attacker_units;
+    }
+
+    (attacker_units_tracker, defender_units_tracker)
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Battle engine");
+    group.measurement_time(Duration::from_secs(5));
+    group.sample_size(20);
+
+    for scale in [1_000u64, 20_000] {
+        let attacker = player(
+            &[
+                (UnitType::LightFighter, scale),
+                (UnitType::Cruiser, scale / 10),
+                (UnitType::Battleship, scale / 20),
+            ],
+            12,
+        );
+        let defender = player(
+            &[
+                (UnitType::MissileLauncher, scale),
+                (UnitType::LightLaser, scale / 2),
+                (UnitType::HeavyLaser, scale / 10),
+            ],
+            12,
+        );
+        group.bench_with_input(BenchmarkId::new("legacy AoS", scale), &scale, |b, _| {
+            let mut rng = FastRng::new(Some(1));
+            b.iter(|| black_box(legacy_battle(&attacker, &defender, &mut rng)));
+        });
+        group.bench_with_input(BenchmarkId::new("SoA", scale), &scale, |b, _| {
+            let mut rng = FastRng::new(Some(1));
+            b.iter(|| black_box(simulate_battle_with_rng(&attacker, &defender, &mut rng)));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,13 +1,16 @@
-use criterion::{Criterion, criterion_group, criterion_main};
+use criterion::measurement::WallTime;
+use criterion::{BenchmarkGroup, BenchmarkId, Criterion, criterion_group, criterion_main};
+use ogame_battle_sim::{BattleRng, CounterRng, FastRng, WyRand as SimWyRand};
 use rand::{RngCore, SeedableRng};
 use std::hint::black_box;
+use std::time::Duration;
 
 use nanorand::Rng;
 use nanorand::wyrand::WyRand;
 
 use rand_xoshiro::Xoshiro256PlusPlus; // Using rand_xoshiro crate here
 
-fn bench_fastrand(c: &mut Criterion) {
+fn bench_fastrand(c: &mut BenchmarkGroup<'_, WallTime>) {
     c.bench_function("fastrand u64", |b| {
         b.iter(|| {
             black_box(fastrand::u64(..));
@@ -15,7 +18,7 @@ fn bench_fastrand(c: &mut Criterion) {
     });
 }
 
-fn bench_nanorand_wyrand(c: &mut Criterion) {
+fn bench_nanorand_wyrand(c: &mut BenchmarkGroup<'_, WallTime>) {
     let mut rng = WyRand::new();
     c.bench_function("nanorand WyRand u64", |b| {
         b.iter(|| {
@@ -24,7 +27,7 @@ fn bench_nanorand_wyrand(c: &mut Criterion) {
     });
 }
 
-fn bench_raw_xoshiro256(c: &mut Criterion) {
+fn bench_raw_xoshiro256(c: &mut BenchmarkGroup<'_, WallTime>) {
     let mut rng = Xoshiro256PlusPlus::seed_from_u64(12345);
     c.bench_function("xoshiro crate Xoshiro256++ u64", |b| {
         b.iter(|| {
@@ -33,6 +36,33 @@ fn bench_raw_xoshiro256(c: &mut Criterion) {
     });
 }
 
+/// The simulator's own backends, through the `BattleRng` trait.
+fn bench_backend<R: BattleRng>(c: &mut BenchmarkGroup<'_, WallTime>, name: &str) {
+    let mut rng = R::seed_from_u64(12345);
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            black_box(rng.next_u64());
+        });
+    });
+}
+
+/// Bounds of the size `FastRng::next` sees when picking targets.
+const BOUNDS: [u64; 3] = [1_000, 1_000_000, (1 << 63) + 1];
+
+fn bench_bounded(c: &mut BenchmarkGroup<'_, WallTime>) {
+    for end in BOUNDS {
+        // the modulo reduction FastRng::next used before it became unbiased
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(12345);
+        c.bench_with_input(BenchmarkId::new("modulo", end), &end, |b, &end| {
+            b.iter(|| black_box(rng.next_u64() % black_box(end)));
+        });
+        let mut rng = FastRng::new(Some(12345));
+        c.bench_with_input(BenchmarkId::new("FastRng::next", end), &end, |b, &end| {
+            b.iter(|| black_box(rng.next(black_box(end))));
+        });
+    }
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("RNG Benchmarks");
     group.measurement_time(Duration::from_secs(3)); // Optional: increase run time for stable results
@@ -40,7 +70,15 @@ fn criterion_benchmark(c: &mut Criterion) {
     bench_fastrand(&mut group);
     bench_nanorand_wyrand(&mut group);
     bench_raw_xoshiro256(&mut group);
+    bench_backend::<FastRng>(&mut group, "BattleRng FastRng u64");
+    bench_backend::<SimWyRand>(&mut group, "BattleRng WyRand u64");
+    bench_backend::<CounterRng>(&mut group, "BattleRng CounterRng u64");
+
+    group.finish();
 
+    let mut group = c.benchmark_group("Bounded integers");
+    group.measurement_time(Duration::from_secs(3));
+    bench_bounded(&mut group);
     group.finish();
 }
 
@@ -0,0 +1,336 @@
+//! Participants given on the command line or in a battle file.
+//!
+//! A participant is written as comma-separated `Type=count` pairs with an
+//! optional `@weapon/shield/armor` research suffix, e.g.
+//! `LightFighter=100,Cruiser=2@12/12/12`. A battle file holds one
+//! participant per line, prefixed with its side:
+//!
+//! ```text
+//! # ACS attack on a defended planet
+//! attacker LightFighter=100,Cruiser=2@12/12/12
+//! attacker Bomber=20@10/10/10
+//! defender MissileLauncher=50,LightLaser=20@11/11/11
+//! ```
+
+use crate::cli::CliError;
+use ogame_battle_sim::{
+    Battle, CombatReport, Engine, Fleet, Player, ReportSection, SimulationInput, TechLevels,
+    UnitType, parse_espionage_report, parse_xml_combat_report, parse_xml_spy_report,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Research levels written as `weapon/shield/armor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TechSpec {
+    pub weapon: u8,
+    pub shield: u8,
+    pub armor: u8,
+}
+
+impl FromStr for TechSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let levels = s
+            .split('/')
+            .map(|level| level.trim().parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid research level in `{s}`: {e}"))?;
+        match levels[..] {
+            [weapon, shield, armor] => Ok(Self {
+                weapon,
+                shield,
+                armor,
+            }),
+            _ => Err(format!("expected weapon/shield/armor, got `{s}`")),
+        }
+    }
+}
+
+impl From<TechSpec> for TechLevels {
+    fn from(t: TechSpec) -> Self {
+        TechLevels {
+            weapon: t.weapon,
+            shield: t.shield,
+            armor: t.armor,
+        }
+    }
+}
+
+/// Parses one participant; `default_tech` applies when the spec has no
+/// `@` research suffix.
+pub fn parse_participant(spec: &str, default_tech: TechSpec) -> Result<Player, CliError> {
+    let (units, tech) = match spec.split_once('@') {
+        Some((units, tech)) => (units, tech.parse().map_err(CliError::Input)?),
+        None => (spec, default_tech),
+    };
+    let mut fleet = HashMap::new();
+    for item in units.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+        let (name, count) = item
+            .split_once('=')
+            .ok_or_else(|| CliError::Input(format!("expected `Type=count`, got `{item}`")))?;
+        let unit_type: UnitType = name
+            .trim()
+            .parse()
+            .map_err(|e| CliError::Input(format!("{e}")))?;
+        let count: u64 = count
+            .trim()
+            .parse()
+            .map_err(|e| CliError::Input(format!("invalid count in `{item}`: {e}")))?;
+        let total = fleet.entry(unit_type).or_insert(0u64);
+        *total = total
+            .checked_add(count)
+            .ok_or_else(|| CliError::Input(format!("too many {unit_type} in `{units}`")))?;
+    }
+    Ok(Player {
+        fleet: Fleet { units: fleet },
+        tech: tech.into(),
+    })
+}
+
+/// Reads a battle file; see the module docs for the format. Participants
+/// without `@` research get the default of their side.
+pub fn read_battle_file(
+    path: &Path,
+    attacker_tech: TechSpec,
+    defender_tech: TechSpec,
+) -> Result<Battle, CliError> {
+    let text = std::fs::read_to_string(path).map_err(|e| CliError::Io(path.to_owned(), e))?;
+    let mut battle = Battle::default();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let at_line = |e: CliError| match e {
+            CliError::Input(msg) => {
+                CliError::Input(format!("{}:{}: {msg}", path.display(), number + 1))
+            }
+            other => other,
+        };
+        let (side, spec) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        match side {
+            "attacker" => battle
+                .attackers
+                .push(parse_participant(spec, attacker_tech).map_err(at_line)?),
+            "defender" => battle
+                .defenders
+                .push(parse_participant(spec, defender_tech).map_err(at_line)?),
+            _ => {
+                return Err(at_line(CliError::Input(format!(
+                    "expected `attacker` or `defender`, got `{side}`"
+                ))));
+            }
+        }
+    }
+    Ok(battle)
+}
+
+/// Most units one side may field in the per-unit engine, which stores
+/// every unit separately in each worker thread (about 20 bytes a unit).
+pub const PER_UNIT_ENGINE_LIMIT: u64 = 100_000_000;
+
+/// Rejects battles `engine` cannot run: sides whose unit total does not
+/// fit in 64 bits, and sides too large for the per-unit engine.
+pub fn check_battle_size(battle: &Battle, engine: Engine) -> Result<(), CliError> {
+    for (name, side) in [
+        ("attacking", &battle.attackers),
+        ("defending", &battle.defenders),
+    ] {
+        let total = side
+            .iter()
+            .flat_map(|p| p.fleet.units.values())
+            .try_fold(0u64, |sum, &n| sum.checked_add(n))
+            .ok_or_else(|| CliError::Input(format!("the {name} side has too many units")))?;
+        if engine == Engine::PerUnit && total > PER_UNIT_ENGINE_LIMIT {
+            return Err(CliError::Input(format!(
+                "the {name} side has {total} units, more than the per-unit engine can hold \
+                 ({PER_UNIT_ENGINE_LIMIT}); use `--engine grouped`"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Reads a JSON battle document.
+pub fn read_json_file(path: &Path) -> Result<SimulationInput, CliError> {
+    let text = std::fs::read_to_string(path).map_err(|e| CliError::Io(path.to_owned(), e))?;
+    SimulationInput::from_json(&text)
+        .map_err(|e| CliError::Input(format!("{}: {e}", path.display())))
+}
+
+/// Reads the defender from an espionage report, either copied out of the
+/// game or in the crate's XML layout, warning on stderr about sections and
+/// entries the report does not cover.
+pub fn read_spy_report(path: &Path) -> Result<Player, CliError> {
+    let text = std::fs::read_to_string(path).map_err(|e| CliError::Io(path.to_owned(), e))?;
+    let in_file = |e: &dyn std::fmt::Display| CliError::Input(format!("{}: {e}", path.display()));
+    let report = if text.trim_start().starts_with('<') {
+        parse_xml_spy_report(&text).map_err(|e| in_file(&e))?
+    } else {
+        parse_espionage_report(&text).map_err(|e| in_file(&e))?
+    };
+    for section in &report.missing {
+        let assumed = match section {
+            ReportSection::Resources => continue,
+            ReportSection::Fleets => "no ships",
+            ReportSection::Defense => "no defenses",
+            ReportSection::Research => "research 0/0/0",
+        };
+        eprintln!(
+            "warning: {}: {section:?} not visible, assuming {assumed}",
+            path.display()
+        );
+    }
+    for entry in &report.unrecognized {
+        eprintln!(
+            "warning: {}: unknown unit `{entry}` ignored",
+            path.display()
+        );
+    }
+    Ok(report.defender)
+}
+
+/// Reads a combat report in the crate's XML layout.
+pub fn read_combat_report(path: &Path) -> Result<CombatReport, CliError> {
+    let text = std::fs::read_to_string(path).map_err(|e| CliError::Io(path.to_owned(), e))?;
+    parse_xml_combat_report(&text).map_err(|e| CliError::Input(format!("{}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NO_TECH: TechSpec = TechSpec {
+        weapon: 0,
+        shield: 0,
+        armor: 0,
+    };
+
+    fn input_error<T: std::fmt::Debug>(result: Result<T, CliError>) -> String {
+        match result {
+            Err(CliError::Input(msg)) => msg,
+            other => panic!("expected an input error, got {other:?}"),
+        }
+    }
+
+    /// Writes `contents` to a file of its own in the temp directory.
+    fn battle_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "ogame-battle-sim-{}-{name}.txt",
+            std::process::id()
+        ));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn parses_research_levels() {
+        assert_eq!(
+            "12/ 11 /10".parse(),
+            Ok(TechSpec {
+                weapon: 12,
+                shield: 11,
+                armor: 10
+            })
+        );
+        assert!("12/11".parse::<TechSpec>().is_err());
+        assert!("12/11/10/9".parse::<TechSpec>().is_err());
+        assert!("12/11/256".parse::<TechSpec>().is_err());
+    }
+
+    #[test]
+    fn parses_a_participant() {
+        let player = parse_participant(
+            "LightFighter=100, Cruiser=2,LightFighter=5@12/11/10",
+            NO_TECH,
+        )
+        .unwrap();
+        assert_eq!(player.fleet.units[&UnitType::LightFighter], 105);
+        assert_eq!(player.fleet.units[&UnitType::Cruiser], 2);
+        assert_eq!(player.tech.shield, 11);
+
+        let player = parse_participant(
+            "Cruiser=2",
+            TechSpec {
+                armor: 7,
+                ..NO_TECH
+            },
+        )
+        .unwrap();
+        assert_eq!(player.tech.armor, 7);
+    }
+
+    #[test]
+    fn rejects_bad_participants() {
+        assert!(input_error(parse_participant("Cruiser", NO_TECH)).contains("Type=count"));
+        assert!(input_error(parse_participant("Cruiser=-1", NO_TECH)).contains("invalid count"));
+        input_error(parse_participant("Titan=1", NO_TECH));
+        input_error(parse_participant("Cruiser=1@1/2", NO_TECH));
+        let overflow = format!("LightFighter={},LightFighter=1", u64::MAX);
+        assert!(input_error(parse_participant(&overflow, NO_TECH)).contains("too many"));
+    }
+
+    #[test]
+    fn reads_a_battle_file() {
+        let path = battle_file(
+            "acs",
+            "# ACS attack\n\
+             attacker LightFighter=100@12/12/12\n\
+             \n\
+             attacker Bomber=20 # default research\n\
+             defender MissileLauncher=50\n",
+        );
+        let battle = read_battle_file(
+            &path,
+            TechSpec {
+                weapon: 5,
+                ..NO_TECH
+            },
+            NO_TECH,
+        )
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(battle.attackers.len(), 2);
+        assert_eq!(battle.attackers[0].tech.weapon, 12);
+        assert_eq!(battle.attackers[1].tech.weapon, 5);
+        assert_eq!(battle.attackers[1].fleet.units[&UnitType::Bomber], 20);
+        assert_eq!(battle.defenders.len(), 1);
+    }
+
+    #[test]
+    fn battle_file_errors_name_the_line() {
+        let path = battle_file("bad", "attacker Cruiser=1\nspectator Cruiser=1\n");
+        let msg = input_error(read_battle_file(&path, NO_TECH, NO_TECH));
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            msg.ends_with(":2: expected `attacker` or `defender`, got `spectator`"),
+            "{msg}"
+        );
+    }
+
+    #[test]
+    fn rejects_sides_the_engine_cannot_hold() {
+        let mut battle = Battle::default();
+        battle.attackers.push(
+            parse_participant(&format!("LightFighter={PER_UNIT_ENGINE_LIMIT}"), NO_TECH).unwrap(),
+        );
+        battle
+            .defenders
+            .push(parse_participant("MissileLauncher=1", NO_TECH).unwrap());
+        assert!(check_battle_size(&battle, Engine::PerUnit).is_ok());
+
+        battle
+            .attackers
+            .push(parse_participant("Cruiser=1", NO_TECH).unwrap());
+        assert!(input_error(check_battle_size(&battle, Engine::PerUnit)).contains("grouped"));
+        assert!(check_battle_size(&battle, Engine::Grouped).is_ok());
+
+        battle
+            .attackers
+            .push(parse_participant(&format!("Cruiser={}", u64::MAX), NO_TECH).unwrap());
+        assert!(input_error(check_battle_size(&battle, Engine::Grouped)).contains("too many"));
+    }
+}
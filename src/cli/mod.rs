@@ -0,0 +1,35 @@
+pub mod input;
+pub mod output;
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Why a command could not run.
+#[derive(Debug)]
+pub enum CliError {
+    /// The battle description is malformed or incomplete.
+    Input(String),
+    /// A file could not be read.
+    Io(PathBuf, io::Error),
+}
+
+impl CliError {
+    /// 2 for bad input, like argument errors; 1 for I/O failures.
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            CliError::Input(_) => ExitCode::from(2),
+            CliError::Io(..) => ExitCode::FAILURE,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Input(msg) => f.write_str(msg),
+            CliError::Io(path, e) => write!(f, "{}: {e}", path.display()),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! Text, CSV and JSON rendering of simulation results.
+
+use ogame_battle_sim::{
+    Battle, BattleOutcome, BattleReport, BattleStatistics, ParticipantReport, Player,
+    ReplayComparison, SimulationResult, UnitSummary, UnitType, combat_report_html,
+    combat_report_text,
+};
+use std::fmt::Write;
+
+/// Output format of the `battle` and `stats` commands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Human-readable tables.
+    #[default]
+    Text,
+    /// One row per side, participant and unit type.
+    Csv,
+    /// The library's JSON result format.
+    Json,
+    /// In-game combat report, round by round; `battle` only.
+    Ingame,
+    /// The in-game combat report as HTML; `battle` only.
+    Html,
+}
+
+impl Format {
+    /// Whether the format needs every round of the battle recorded.
+    pub fn is_combat_report(self) -> bool {
+        matches!(self, Format::Ingame | Format::Html)
+    }
+}
+
+fn outcome_text(outcome: BattleOutcome) -> &'static str {
+    match outcome {
+        BattleOutcome::AttackerWin => "attacker wins",
+        BattleOutcome::DefenderWin => "defender wins",
+        BattleOutcome::Draw => "draw",
+    }
+}
+
+pub fn battle_report(battle: &Battle, report: &BattleReport, format: Format) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Text => {
+            let _ = writeln!(
+                out,
+                "Outcome: {} after {} round(s)",
+                outcome_text(report.outcome()),
+                report.rounds_fought
+            );
+            let sides = [
+                ("Attacker", &report.attackers),
+                ("Defender", &report.defenders),
+            ];
+            for (side, participants) in sides {
+                for (i, p) in participants.iter().enumerate() {
+                    participant_table(&mut out, &format!("{side} {}", i + 1), p);
+                }
+            }
+            let _ = writeln!(
+                out,
+                "\nDebris: {:.0} metal, {:.0} crystal (moon chance {} %)",
+                report.debris.metal, report.debris.crystal, report.moon_chance
+            );
+        }
+        Format::Csv => {
+            out.push_str("side,participant,unit,initial,remaining,rebuilt\n");
+            let sides = [
+                ("attacker", &report.attackers),
+                ("defender", &report.defenders),
+            ];
+            for (side, participants) in sides {
+                for (i, p) in participants.iter().enumerate() {
+                    for (&ut, ((&initial, &remaining), &rebuilt)) in
+                        UnitType::iter().zip(p.initial.iter().zip(&p.remaining).zip(&p.rebuilt))
+                    {
+                        if initial > 0 {
+                            let _ = writeln!(
+                                out,
+                                "{side},{},{ut},{initial},{remaining},{rebuilt}",
+                                i + 1
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Format::Json => out = SimulationResult::battle(report).to_json() + "\n",
+        Format::Ingame => out = combat_report_text(battle, report),
+        Format::Html => out = combat_report_html(battle, report),
+    }
+    out
+}
+
+fn participant_table(out: &mut String, title: &str, p: &ParticipantReport) {
+    let _ = writeln!(
+        out,
+        "\n{title:<24}{:>12}{:>12}{:>12}{:>12}",
+        "initial", "remaining", "lost", "rebuilt"
+    );
+    let lost = p.lost();
+    for (&ut, &initial) in UnitType::iter().zip(&p.initial) {
+        if initial == 0 {
+            continue;
+        }
+        let i = ut as usize;
+        let _ = writeln!(
+            out,
+            "  {:<22}{:>12}{:>12}{:>12}{:>12}",
+            ut.name(),
+            initial,
+            p.remaining[i],
+            lost[i],
+            p.rebuilt[i]
+        );
+    }
+}
+
+/// Unit types fielded by any participant of a side, with their summaries.
+fn fielded<'a>(
+    players: &[Player],
+    summaries: &'a [UnitSummary; UnitType::count()],
+) -> Vec<(UnitType, &'a UnitSummary)> {
+    UnitType::iter()
+        .filter(|ut| players.iter().any(|p| p.fleet.units.get(ut) > Some(&0)))
+        .map(|&ut| (ut, &summaries[ut as usize]))
+        .collect()
+}
+
+pub fn statistics(battle: &Battle, stats: &BattleStatistics, format: Format) -> String {
+    let mut out = String::new();
+    let sides = [
+        ("attacker", fielded(&battle.attackers, &stats.attacker)),
+        ("defender", fielded(&battle.defenders, &stats.defender)),
+    ];
+    match format {
+        Format::Text => {
+            let _ = writeln!(out, "Trials: {}", stats.trials);
+            let _ = writeln!(
+                out,
+                "Attacker wins {:.1} %, defender wins {:.1} %, draws {:.1} %",
+                stats.attacker_win * 100.0,
+                stats.defender_win * 100.0,
+                stats.draw * 100.0
+            );
+            let _ = writeln!(out, "Mean rounds fought: {:.2}", stats.expected_rounds);
+            for (side, units) in &sides {
+                let _ = write!(
+                    out,
+                    "\nSurviving {side} units{:>10}{:>10}{:>10}",
+                    "mean", "std dev", "min"
+                );
+                if let Some((_, s)) = units.first() {
+                    for (p, _) in &s.percentiles {
+                        let _ = write!(out, "{:>10}", format!("p{p}"));
+                    }
+                }
+                let _ = writeln!(out, "{:>10}", "max");
+                for (ut, s) in units {
+                    let _ = write!(
+                        out,
+                        "  {:<22}{:>10.2}{:>10.2}{:>10}",
+                        ut.name(),
+                        s.mean,
+                        s.std_dev,
+                        s.min
+                    );
+                    for (_, v) in &s.percentiles {
+                        let _ = write!(out, "{v:>10}");
+                    }
+                    let _ = writeln!(out, "{:>10}", s.max);
+                }
+            }
+            let _ = writeln!(
+                out,
+                "\nExpected debris: {:.0} metal, {:.0} crystal",
+                stats.expected_debris.metal, stats.expected_debris.crystal
+            );
+            let _ = writeln!(
+                out,
+                "Expected losses: attacker {:.0}, defender {:.0}",
+                stats.expected_attacker_losses, stats.expected_defender_losses
+            );
+            let _ = writeln!(
+                out,
+                "Moon chance: {:.2} %",
+                stats.expected_moon_chance * 100.0
+            );
+        }
+        Format::Csv => {
+            out.push_str("side,unit,mean,std_dev,min,max");
+            if let Some((_, s)) = sides.iter().flat_map(|(_, u)| u).next() {
+                for (p, _) in &s.percentiles {
+                    let _ = write!(out, ",p{p}");
+                }
+            }
+            out.push('\n');
+            for (side, units) in &sides {
+                for (ut, s) in units {
+                    let _ = write!(
+                        out,
+                        "{side},{ut},{},{},{},{}",
+                        s.mean, s.std_dev, s.min, s.max
+                    );
+                    for (_, v) in &s.percentiles {
+                        let _ = write!(out, ",{v}");
+                    }
+                    out.push('\n');
+                }
+            }
+        }
+        Format::Json => out = SimulationResult::Statistics(stats).to_json() + "\n",
+        Format::Ingame | Format::Html => unreachable!("rejected by the `stats` command"),
+    }
+    out
+}
+
+pub fn replay(comparison: &ReplayComparison) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Recorded outcome: {} ({:.1} % of {} simulated battles)",
+        outcome_text(comparison.outcome),
+        comparison.outcome_probability * 100.0,
+        comparison.statistics.trials
+    );
+    if let (Some(rounds), Some(p)) = (comparison.rounds, comparison.rounds_probability) {
+        let _ = writeln!(
+            out,
+            "Recorded rounds: {rounds} ({:.1} % of simulated battles, mean {:.2})",
+            p * 100.0,
+            comparison.statistics.expected_rounds
+        );
+    }
+    let sides = [
+        ("attacker", &comparison.attacker),
+        ("defender", &comparison.defender),
+    ];
+    for (side, units) in sides {
+        let _ = writeln!(
+            out,
+            "\nSurviving {side} units{:>10}{:>10}{:>10}{:>10}{:>10}{:>10}",
+            "recorded", "mean", "std dev", "min", "max", "sigma"
+        );
+        for u in units {
+            let _ = writeln!(
+                out,
+                "  {:<22}{:>10}{:>10.2}{:>10.2}{:>10}{:>10}{:>10.2}{}",
+                u.unit_type.name(),
+                u.actual,
+                u.simulated.mean,
+                u.simulated.std_dev,
+                u.simulated.min,
+                u.simulated.max,
+                u.deviation(),
+                if u.within_simulated_range() {
+                    ""
+                } else {
+                    "  outside simulated range"
+                }
+            );
+        }
+    }
+    out
+}
@@ -0,0 +1,420 @@
+//! Battle results laid out like the combat reports of the English game
+//! client, as plain text or as HTML for forum posts.
+//!
+//! The round by round part needs a report from one of the `_detailed`
+//! entry points; for any other report only the fleets before and after the
+//! battle are shown.
+
+use crate::simulator::profit::side_value;
+use crate::simulator::report::{BattleOutcome, BattleReport, FireStats, ParticipantReport};
+use crate::types::battle::Battle;
+use crate::types::player::{Player, TechLevels};
+use crate::types::unit::Unit;
+use crate::types::unit_type::UnitType;
+use std::fmt::Write;
+
+/// Fleet of one participant at one point of the battle.
+struct FleetTable {
+    title: String,
+    tech: TechLevels,
+    /// `(type, count, weapons, shields, armour)` of every type still there.
+    columns: Vec<(UnitType, u64, f64, f64, f64)>,
+}
+
+impl FleetTable {
+    fn new(title: String, player: &Player, counts: &[u64; UnitType::count()]) -> Self {
+        let tech = player.tech.clone();
+        let columns = UnitType::iter()
+            .zip(counts)
+            .filter(|(_, n)| **n > 0)
+            .map(|(&ut, &n)| {
+                (
+                    ut,
+                    n,
+                    Unit::attack_pts(ut, &tech),
+                    Unit::shield_pts(ut, &tech),
+                    Unit::hull_pts(ut, &tech),
+                )
+            })
+            .collect();
+        Self {
+            title,
+            tech,
+            columns,
+        }
+    }
+
+    fn tech_line(&self) -> String {
+        format!(
+            "Weapons: {}% Shields: {}% Armour: {}%",
+            self.tech.weapon as u32 * 10,
+            self.tech.shield as u32 * 10,
+            self.tech.armor as u32 * 10
+        )
+    }
+
+    /// Row label followed by one cell per column.
+    fn rows(&self) -> [(&'static str, Vec<String>); 5] {
+        let cells = |f: fn(&(UnitType, u64, f64, f64, f64)) -> String| {
+            self.columns.iter().map(f).collect::<Vec<_>>()
+        };
+        [
+            ("Type", cells(|c| c.0.ingame_name().to_owned())),
+            ("Total", cells(|c| thousands(c.1))),
+            ("Weapons", cells(|c| thousands(c.2.round() as u64))),
+            ("Shields", cells(|c| thousands(c.3.round() as u64))),
+            ("Armour", cells(|c| thousands(c.4.round() as u64))),
+        ]
+    }
+}
+
+/// A piece of the report, rendered the same way by both layouts.
+enum Block {
+    Heading(String),
+    Fleets(Vec<FleetTable>),
+    Text(String),
+}
+
+/// `1234567` as `1.234.567`, the way the game prints numbers.
+fn thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push('.');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn fleets(
+    battle: &Battle,
+    attackers: &[[u64; UnitType::count()]],
+    defenders: &[[u64; UnitType::count()]],
+) -> Block {
+    let side = |name: &str, players: &[Player], counts: &[[u64; UnitType::count()]]| {
+        players
+            .iter()
+            .zip(counts)
+            .enumerate()
+            .map(|(i, (player, counts))| {
+                let title = if players.len() > 1 {
+                    format!("{name} {}", i + 1)
+                } else {
+                    name.to_owned()
+                };
+                FleetTable::new(title, player, counts)
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut tables = side("Attacker", &battle.attackers, attackers);
+    tables.extend(side("Defender", &battle.defenders, defenders));
+    Block::Fleets(tables)
+}
+
+fn volley(fleet: &str, target: &str, fire: &FireStats) -> Block {
+    Block::Text(format!(
+        "The {fleet} fleet fires a total of {} times with the total firepower of {} upon the \
+         {target}. The {target}'s shields absorb {} damage points.",
+        thousands(fire.shots),
+        thousands(fire.damage.round() as u64),
+        thousands(fire.absorbed.round() as u64)
+    ))
+}
+
+fn unit_total(
+    participants: &[ParticipantReport],
+    lost: fn(&ParticipantReport) -> [u64; UnitType::count()],
+) -> u64 {
+    side_value(participants, lost).total().round() as u64
+}
+
+fn blocks(battle: &Battle, report: &BattleReport) -> Vec<Block> {
+    let initial = |ps: &[ParticipantReport]| ps.iter().map(|p| p.initial).collect::<Vec<_>>();
+    let mut blocks = vec![
+        Block::Heading("Combat report".into()),
+        Block::Text("The following fleets met in battle:".into()),
+        fleets(
+            battle,
+            &initial(&report.attackers),
+            &initial(&report.defenders),
+        ),
+    ];
+
+    for (n, round) in report.rounds.iter().enumerate() {
+        blocks.push(volley("attacking", "defender", &round.attacker_fire));
+        blocks.push(volley("defending", "attacker", &round.defender_fire));
+        blocks.push(Block::Heading(format!("After round {}", n + 1)));
+        blocks.push(fleets(battle, &round.attackers, &round.defenders));
+    }
+    if report.rounds.is_empty() {
+        let remaining =
+            |ps: &[ParticipantReport]| ps.iter().map(|p| p.remaining).collect::<Vec<_>>();
+        blocks.push(Block::Heading("After the battle".into()));
+        blocks.push(fleets(
+            battle,
+            &remaining(&report.attackers),
+            &remaining(&report.defenders),
+        ));
+    }
+
+    blocks.push(Block::Text(
+        match report.outcome() {
+            BattleOutcome::AttackerWin => "The attacker has won the battle!",
+            BattleOutcome::DefenderWin => "The defender has won the battle!",
+            BattleOutcome::Draw => {
+                "The battle ended in a draw, both fleets withdraw to their home planets."
+            }
+        }
+        .into(),
+    ));
+    blocks.push(Block::Text(format!(
+        "The attacker lost a total of {} units.",
+        thousands(unit_total(&report.attackers, ParticipantReport::lost))
+    )));
+    blocks.push(Block::Text(format!(
+        "The defender lost a total of {} units.",
+        thousands(unit_total(
+            &report.defenders,
+            ParticipantReport::permanently_lost
+        ))
+    )));
+
+    let mut rebuilt = [0u64; UnitType::count()];
+    for p in &report.defenders {
+        for (r, &n) in rebuilt.iter_mut().zip(&p.rebuilt) {
+            *r += n;
+        }
+    }
+    let repaired: Vec<_> = UnitType::iter()
+        .zip(&rebuilt)
+        .filter(|(_, n)| **n > 0)
+        .map(|(ut, &n)| format!("{} {}", thousands(n), ut.ingame_name()))
+        .collect();
+    if !repaired.is_empty() {
+        blocks.push(Block::Text(format!(
+            "{} could be repaired.",
+            repaired.join(", ")
+        )));
+    }
+
+    blocks.push(Block::Text(format!(
+        "At these space coordinates now float {} metal and {} crystal.",
+        thousands(report.debris.metal.round() as u64),
+        thousands(report.debris.crystal.round() as u64)
+    )));
+    if report.moon_chance > 0 {
+        blocks.push(Block::Text(format!(
+            "The chance for a moon to be created is {} %.",
+            report.moon_chance
+        )));
+    }
+    blocks
+}
+
+/// Renders `report`, a battle between the participants of `battle`, in the
+/// text layout of an in-game combat report.
+pub fn combat_report_text(battle: &Battle, report: &BattleReport) -> String {
+    let mut out = String::new();
+    for block in blocks(battle, report) {
+        match block {
+            Block::Heading(h) => {
+                let _ = writeln!(out, "{h}\n");
+            }
+            Block::Text(t) => {
+                let _ = writeln!(out, "{t}\n");
+            }
+            Block::Fleets(tables) => {
+                for table in tables {
+                    let _ = writeln!(out, "{}\n{}", table.title, table.tech_line());
+                    if table.columns.is_empty() {
+                        let _ = writeln!(out, "Destroyed!\n");
+                        continue;
+                    }
+                    let rows = table.rows();
+                    let width = rows
+                        .iter()
+                        .flat_map(|(_, cells)| cells.iter().map(String::len))
+                        .max()
+                        .unwrap_or(0)
+                        + 2;
+                    for (label, cells) in rows {
+                        let _ = write!(out, "{label:<8}");
+                        for cell in cells {
+                            let _ = write!(out, "{cell:>width$}");
+                        }
+                        out.push('\n');
+                    }
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    out
+}
+
+/// `text` with the characters HTML gives a meaning escaped.
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Same as [`combat_report_text`], as an HTML fragment of headings,
+/// paragraphs and one table per fleet.
+pub fn combat_report_html(battle: &Battle, report: &BattleReport) -> String {
+    let mut out = String::from("<div class=\"combat-report\">\n");
+    for block in blocks(battle, report) {
+        match block {
+            Block::Heading(h) => {
+                let _ = writeln!(out, "<h3>{}</h3>", escape_html(&h));
+            }
+            Block::Text(t) => {
+                let _ = writeln!(out, "<p>{}</p>", escape_html(&t));
+            }
+            Block::Fleets(tables) => {
+                for table in tables {
+                    let _ = writeln!(out, "<table class=\"fleet\">");
+                    let _ = writeln!(
+                        out,
+                        "<caption>{}<br>{}</caption>",
+                        escape_html(&table.title),
+                        escape_html(&table.tech_line())
+                    );
+                    if table.columns.is_empty() {
+                        let _ = writeln!(out, "<tr><td>Destroyed!</td></tr>");
+                    } else {
+                        for (label, cells) in table.rows() {
+                            let _ = write!(out, "<tr><th>{label}</th>");
+                            for cell in cells {
+                                let _ = write!(out, "<td>{}</td>", escape_html(&cell));
+                            }
+                            out.push_str("</tr>\n");
+                        }
+                    }
+                    out.push_str("</table>\n");
+                }
+            }
+        }
+    }
+    out.push_str("</div>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::debris::DebrisField;
+    use crate::simulator::report::RoundReport;
+    use crate::test_util::battle;
+
+    fn counts(units: &[(UnitType, u64)]) -> [u64; UnitType::count()] {
+        let mut counts = [0; UnitType::count()];
+        for &(ut, n) in units {
+            counts[ut as usize] = n;
+        }
+        counts
+    }
+
+    /// 1 200 light fighters wipe out 10 rocket launchers in one round.
+    fn sample(moon_chance: u32) -> (Battle, BattleReport) {
+        let attackers = [(UnitType::LightFighter, 1_200)];
+        let defenders = [(UnitType::MissileLauncher, 10)];
+        let survivors = counts(&[(UnitType::LightFighter, 1_195)]);
+        let report = BattleReport {
+            attackers: vec![ParticipantReport {
+                initial: counts(&attackers),
+                remaining: survivors,
+                rebuilt: [0; UnitType::count()],
+            }],
+            defenders: vec![ParticipantReport {
+                initial: counts(&defenders),
+                remaining: [0; UnitType::count()],
+                rebuilt: counts(&[(UnitType::MissileLauncher, 7)]),
+            }],
+            rounds: vec![RoundReport {
+                attackers: vec![survivors],
+                defenders: vec![[0; UnitType::count()]],
+                attacker_fire: FireStats {
+                    shots: 1_200,
+                    damage: 60_000.0,
+                    absorbed: 1_234.4,
+                    bounced: 0,
+                },
+                defender_fire: FireStats {
+                    shots: 10,
+                    damage: 800.0,
+                    absorbed: 100.0,
+                    bounced: 0,
+                },
+            }],
+            rounds_fought: 1,
+            debris: DebrisField {
+                metal: 4_500.0,
+                crystal: 1_500.0,
+            },
+            moon_chance,
+        };
+        (battle(&attackers, &defenders), report)
+    }
+
+    #[test]
+    fn formats_numbers_like_the_game() {
+        assert_eq!(thousands(0), "0");
+        assert_eq!(thousands(999), "999");
+        assert_eq!(thousands(1_000), "1.000");
+        assert_eq!(thousands(1_234_567), "1.234.567");
+    }
+
+    #[test]
+    fn text_report_follows_the_game_layout() {
+        let (battle, report) = sample(0);
+        let text = combat_report_text(&battle, &report);
+        assert!(text.starts_with("Combat report\n"));
+        assert!(text.contains(
+            "The attacking fleet fires a total of 1.200 times with the total firepower of \
+             60.000 upon the defender. The defender's shields absorb 1.234 damage points."
+        ));
+        assert!(text.contains("The defending fleet fires a total of 10 times"));
+        assert!(text.contains("After round 1"));
+        assert!(text.contains("Defender\nWeapons: 0% Shields: 0% Armour: 0%\nDestroyed!\n"));
+        assert!(text.contains("The attacker has won the battle!"));
+        assert!(text.contains("The attacker lost a total of 20.000 units."));
+        assert!(text.contains("7 Rocket Launcher could be repaired."));
+        assert!(text.contains("now float 4.500 metal and 1.500 crystal."));
+        assert!(!text.contains("moon"));
+    }
+
+    #[test]
+    fn moon_line_only_with_a_chance() {
+        let (battle, report) = sample(3);
+        let text = combat_report_text(&battle, &report);
+        assert!(text.contains("The chance for a moon to be created is 3 %."));
+    }
+
+    #[test]
+    fn html_report_escapes_text() {
+        assert_eq!(
+            escape_html(r#"<b>"Tom" & 'Jerry'</b>"#),
+            "&lt;b&gt;&quot;Tom&quot; &amp; &#39;Jerry&#39;&lt;/b&gt;"
+        );
+        let (battle, report) = sample(3);
+        let html = combat_report_html(&battle, &report);
+        assert!(html.starts_with("<div class=\"combat-report\">\n<h3>Combat report</h3>\n"));
+        assert!(html.contains("The defender&#39;s shields absorb"));
+        assert!(!html.contains('\''));
+        assert!(html.contains("<tr><th>Total</th><td>1.200</td></tr>"));
+        assert!(html.contains("<tr><td>Destroyed!</td></tr>"));
+        assert!(html.ends_with("</div>\n"));
+    }
+}
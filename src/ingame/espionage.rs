@@ -0,0 +1,321 @@
+//! Espionage reports copied as text out of the English game client.
+//!
+//! A report is a sequence of sections, each a header line followed by
+//! `name value` entries:
+//!
+//! ```text
+//! Resources
+//! Metal: 1.234.567
+//! Crystal: 234.567
+//! Deuterium: 45.678
+//!
+//! Fleets
+//! Light Fighter 120
+//! Espionage Probe 3
+//!
+//! Defense
+//! Rocket Launcher 50
+//!
+//! Research
+//! Weapons Technology 12
+//! Shielding Technology 11
+//! Armour Technology 12
+//! ```
+//!
+//! Name and value may also sit on consecutive lines, as they do when the
+//! report is copied from the newer client layout. The game leaves out the
+//! sections the probes could not see, and the parser reports those instead
+//! of assuming the defender has nothing there.
+
+use crate::types::player::{Fleet, Player, TechLevels};
+use crate::types::resources::Resources;
+use crate::types::unit_type::UnitType;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Section of an espionage report that matters for a battle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportSection {
+    Resources,
+    Fleets,
+    Defense,
+    Research,
+}
+
+/// What an espionage report tells about the defender.
+#[derive(Debug, Clone)]
+pub struct EspionageReport {
+    /// Defender built from the visible sections; units and research of
+    /// sections in `missing` are zero. Missiles are left out, they do not
+    /// fight.
+    pub defender: Player,
+    /// Resources on the planet, if that section was visible.
+    pub resources: Option<Resources>,
+    /// Sections the report does not show, in the order a full report
+    /// lists them.
+    pub missing: Vec<ReportSection>,
+    /// Entries of the fleet and defense sections that name no known unit,
+    /// and names in any section that no value followed.
+    pub unrecognized: Vec<String>,
+}
+
+impl EspionageReport {
+    /// `true` when every section that matters for a battle was visible.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Text without any resources, fleet, defense or research section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotAnEspionageReport;
+
+impl fmt::Display for NotAnEspionageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("no espionage report sections found")
+    }
+}
+
+impl std::error::Error for NotAnEspionageReport {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Relevant(ReportSection),
+    /// Buildings and anything else the simulator has no use for.
+    Ignored,
+}
+
+fn section_header(line: &str) -> Option<Section> {
+    let header = line.trim_end_matches(':').trim().to_ascii_lowercase();
+    Some(match header.as_str() {
+        "resources" => Section::Relevant(ReportSection::Resources),
+        "fleet" | "fleets" | "ships" => Section::Relevant(ReportSection::Fleets),
+        "defense" | "defence" | "defenses" | "defences" => {
+            Section::Relevant(ReportSection::Defense)
+        }
+        "research" => Section::Relevant(ReportSection::Research),
+        "buildings" => Section::Ignored,
+        // footer line, closes the last section
+        h if h.starts_with("chance of counter-espionage") => Section::Ignored,
+        _ => return None,
+    })
+}
+
+/// Parses a whole-number game value, with `.` or `,` as thousands separator.
+fn parse_amount(s: &str) -> Option<u64> {
+    let digits: String = s.chars().filter(|c| !matches!(c, '.' | ',')).collect();
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Splits `"Light Fighter: 1.200"` into its name and value; the value is
+/// `None` when the line holds only a name.
+fn split_entry(line: &str) -> (&str, Option<u64>) {
+    match line.rsplit_once(|c: char| c.is_whitespace() || c == ':') {
+        Some((name, value)) => match parse_amount(value.trim()) {
+            Some(amount) => (name.trim().trim_end_matches(':').trim(), Some(amount)),
+            None => (line, None),
+        },
+        None => (line, None),
+    }
+}
+
+/// Builds the defender of a battle from the text of an espionage report.
+pub fn parse_espionage_report(text: &str) -> Result<EspionageReport, NotAnEspionageReport> {
+    let mut seen = Vec::new();
+    let mut section = None;
+    let mut pending: Option<&str> = None;
+    let mut units = HashMap::new();
+    let mut resources = Resources::default();
+    let mut tech = TechLevels {
+        weapon: 0,
+        shield: 0,
+        armor: 0,
+    };
+    let mut unrecognized = Vec::new();
+
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(header) = section_header(line) {
+            if let Section::Relevant(s) = header {
+                seen.push(s);
+            }
+            section = Some(header);
+            unrecognized.extend(pending.take().map(str::to_owned));
+            continue;
+        }
+        let Some(Section::Relevant(current)) = section else {
+            continue;
+        };
+        let (name, value) = match (pending.take(), parse_amount(line)) {
+            // value line completing a name on the previous line
+            (Some(name), Some(value)) => (name, value),
+            (previous, _) => {
+                unrecognized.extend(previous.map(str::to_owned));
+                match split_entry(line) {
+                    (name, Some(value)) => (name, value),
+                    (name, None) => {
+                        pending = Some(name.trim_end_matches(':').trim());
+                        continue;
+                    }
+                }
+            }
+        };
+        match current {
+            ReportSection::Resources => match name.to_ascii_lowercase().as_str() {
+                "metal" => resources.metal = value as f64,
+                "crystal" => resources.crystal = value as f64,
+                "deuterium" => resources.deuterium = value as f64,
+                _ => {}
+            },
+            ReportSection::Fleets | ReportSection::Defense => {
+                match UnitType::from_ingame_name(name) {
+                    Some(ut) if ut.is_missile() => {}
+                    Some(ut) => *units.entry(ut).or_insert(0) += value,
+                    None => unrecognized.push(format!("{name} {value}")),
+                }
+            }
+            ReportSection::Research => {
+                let level = u8::try_from(value).unwrap_or(u8::MAX);
+                match name.to_ascii_lowercase().as_str() {
+                    "weapons technology" | "weapon technology" => tech.weapon = level,
+                    "shielding technology" | "shield technology" => tech.shield = level,
+                    "armour technology" | "armor technology" => tech.armor = level,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    unrecognized.extend(pending.map(str::to_owned));
+    if seen.is_empty() {
+        return Err(NotAnEspionageReport);
+    }
+    let missing = [
+        ReportSection::Resources,
+        ReportSection::Fleets,
+        ReportSection::Defense,
+        ReportSection::Research,
+    ]
+    .into_iter()
+    .filter(|s| !seen.contains(s))
+    .collect();
+    Ok(EspionageReport {
+        defender: Player {
+            fleet: Fleet { units },
+            tech,
+        },
+        resources: seen
+            .contains(&ReportSection::Resources)
+            .then_some(resources),
+        missing,
+        unrecognized,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL_REPORT: &str = "\
+Resources
+Metal: 1.234.567
+Crystal: 234.567
+Deuterium: 45.678
+
+Fleets
+Light Fighter
+120
+Espionage Probe 3
+
+Defense
+Rocket Launcher 50
+Anti-Ballistic Missiles 20
+Interplanetary Missiles 5
+
+Research
+Weapons Technology 12
+Shielding Technology 11
+Armour Technology 10
+
+Chance of counter-espionage: 0%
+";
+
+    #[test]
+    fn parses_every_section() {
+        let report = parse_espionage_report(FULL_REPORT).unwrap();
+        assert!(report.is_complete());
+        assert_eq!(
+            report.resources,
+            Some(Resources::new(1_234_567.0, 234_567.0, 45_678.0))
+        );
+        let units = &report.defender.fleet.units;
+        assert_eq!(units.get(&UnitType::LightFighter), Some(&120));
+        assert_eq!(units.get(&UnitType::EspionageProbe), Some(&3));
+        assert_eq!(units.get(&UnitType::MissileLauncher), Some(&50));
+        let tech = &report.defender.tech;
+        assert_eq!((tech.weapon, tech.shield, tech.armor), (12, 11, 10));
+        assert!(report.unrecognized.is_empty());
+    }
+
+    #[test]
+    fn leaves_missiles_out_of_the_defender() {
+        let report = parse_espionage_report(FULL_REPORT).unwrap();
+        let units = &report.defender.fleet.units;
+        assert!(!units.contains_key(&UnitType::AntiBallisticMissiles));
+        assert!(!units.contains_key(&UnitType::InterplanetaryMissiles));
+        assert_eq!(units.len(), 3);
+    }
+
+    #[test]
+    fn reports_hidden_sections() {
+        let report = parse_espionage_report("Resources\nMetal: 10\n\nFleets\n").unwrap();
+        assert_eq!(
+            report.missing,
+            [ReportSection::Defense, ReportSection::Research]
+        );
+        assert_eq!(report.defender.tech.weapon, 0);
+    }
+
+    #[test]
+    fn reads_names_and_values_on_separate_lines() {
+        let report = parse_espionage_report(
+            "Resources\nMetal:\n1.000\nCrystal:\n500\nDeuterium:\n250\n\
+             Fleets\nLight Fighter:\n7\n\
+             Research\nWeapons Technology:\n10\nShielding Technology:\n9\n\
+             Armour Technology:\n8\n",
+        )
+        .unwrap();
+        assert_eq!(
+            report.resources,
+            Some(Resources::new(1_000.0, 500.0, 250.0))
+        );
+        assert_eq!(
+            report.defender.fleet.units.get(&UnitType::LightFighter),
+            Some(&7)
+        );
+        let tech = &report.defender.tech;
+        assert_eq!((tech.weapon, tech.shield, tech.armor), (10, 9, 8));
+        assert!(report.unrecognized.is_empty());
+    }
+
+    #[test]
+    fn keeps_names_without_a_value() {
+        let report =
+            parse_espionage_report("Fleets\nLight Fighter\nCruiser\n4\nBattleship\n").unwrap();
+        assert_eq!(
+            report.defender.fleet.units.get(&UnitType::Cruiser),
+            Some(&4)
+        );
+        assert_eq!(report.unrecognized, ["Light Fighter", "Battleship"]);
+    }
+
+    #[test]
+    fn rejects_text_without_sections() {
+        assert_eq!(
+            parse_espionage_report("hello").unwrap_err(),
+            NotAnEspionageReport
+        );
+    }
+}
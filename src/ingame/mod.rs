@@ -0,0 +1,12 @@
+//! Reports from the game itself.
+
+pub mod combat_report;
+pub mod espionage;
+pub mod xml;
+
+pub use combat_report::{combat_report_html, combat_report_text};
+pub use espionage::{EspionageReport, NotAnEspionageReport, ReportSection, parse_espionage_report};
+pub use xml::{
+    CombatReport, ReplayComparison, UnitComparison, XmlReportError, parse_xml_combat_report,
+    parse_xml_spy_report,
+};
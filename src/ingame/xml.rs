@@ -0,0 +1,526 @@
+//! Spy and combat reports in an XML layout of this crate's own, for tools
+//! that collect reports and want them simulated.
+//!
+//! This is not the format the game's API returns, and nothing here fetches
+//! or resolves `sr-`/`cr-` API keys; a report from the game has to be
+//! converted into this layout first. Units and research are identified by
+//! the game's numeric technology ids (see [`UnitType::tech_id`]); research
+//! ids are 109 (weapons), 110 (shielding) and 111 (armour).
+//!
+//! A spy report lists the sections the probes could see; absent elements are
+//! reported as missing, like [`parse_espionage_report`] does for text:
+//!
+//! ```xml
+//! <spy_report>
+//!   <resources metal="1234567" crystal="234567" deuterium="45678"/>
+//!   <ships>
+//!     <unit type="204" count="120"/>
+//!   </ships>
+//!   <defense>
+//!     <unit type="401" count="50"/>
+//!   </defense>
+//!   <research>
+//!     <tech type="109" level="12"/>
+//!     <tech type="110" level="11"/>
+//!     <tech type="111" level="12"/>
+//!   </research>
+//! </spy_report>
+//! ```
+//!
+//! A combat report holds one element per participant, with the research
+//! bonus in percent as the game shows it and each unit's count before and
+//! after the battle:
+//!
+//! ```xml
+//! <combat_report rounds="3">
+//!   <attacker weapons="120" shields="110" armor="120">
+//!     <unit type="206" count="200" remaining="196"/>
+//!   </attacker>
+//!   <defender weapons="110" shields="110" armor="110">
+//!     <unit type="204" count="120" remaining="0"/>
+//!     <unit type="401" count="50" remaining="0"/>
+//!   </defender>
+//! </combat_report>
+//! ```
+//!
+//! [`parse_espionage_report`]: super::parse_espionage_report
+
+use crate::ingame::espionage::{EspionageReport, ReportSection};
+use crate::simulator::monte_carlo::{MonteCarloConfig, simulate_battles_stats};
+use crate::simulator::report::BattleOutcome;
+use crate::simulator::stats::{BattleStatistics, UnitSummary};
+use crate::types::battle::Battle;
+use crate::types::player::{Fleet, Player, TechLevels};
+use crate::types::resources::Resources;
+use crate::types::unit_type::UnitType;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+const WEAPONS_TECHNOLOGY: u16 = 109;
+const SHIELDING_TECHNOLOGY: u16 = 110;
+const ARMOUR_TECHNOLOGY: u16 = 111;
+
+/// Why an XML report could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XmlReportError {
+    /// The file is not well-formed XML.
+    Xml(String),
+    /// The root element is not the expected kind of report.
+    WrongReport {
+        expected: &'static str,
+        found: String,
+    },
+    /// An attribute is absent or not a whole number.
+    BadAttribute {
+        element: String,
+        attribute: &'static str,
+    },
+    /// A combat report unit with an id the simulator does not know.
+    UnknownUnit(u16),
+    /// A combat report without attacker or without defender.
+    MissingSide(&'static str),
+}
+
+impl fmt::Display for XmlReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XmlReportError::Xml(e) => write!(f, "invalid XML: {e}"),
+            XmlReportError::WrongReport { expected, found } => {
+                write!(f, "expected a <{expected}> report, found <{found}>")
+            }
+            XmlReportError::BadAttribute { element, attribute } => {
+                write!(f, "<{element}> needs a numeric `{attribute}` attribute")
+            }
+            XmlReportError::UnknownUnit(id) => write!(f, "unknown unit id {id}"),
+            XmlReportError::MissingSide(side) => write!(f, "combat report has no {side}"),
+        }
+    }
+}
+
+impl std::error::Error for XmlReportError {}
+
+impl From<roxmltree::Error> for XmlReportError {
+    fn from(e: roxmltree::Error) -> Self {
+        XmlReportError::Xml(e.to_string())
+    }
+}
+
+fn number<T: FromStr>(node: Node, attribute: &'static str) -> Result<T, XmlReportError> {
+    node.attribute(attribute)
+        .and_then(|v| v.trim().parse().ok())
+        .ok_or_else(|| XmlReportError::BadAttribute {
+            element: node.tag_name().name().to_owned(),
+            attribute,
+        })
+}
+
+fn root<'a, 'i>(
+    doc: &'a Document<'i>,
+    expected: &'static str,
+) -> Result<Node<'a, 'i>, XmlReportError> {
+    let root = doc.root_element();
+    if root.tag_name().name() != expected {
+        return Err(XmlReportError::WrongReport {
+            expected,
+            found: root.tag_name().name().to_owned(),
+        });
+    }
+    Ok(root)
+}
+
+fn children<'a, 'i>(node: Node<'a, 'i>, name: &'static str) -> impl Iterator<Item = Node<'a, 'i>> {
+    node.children()
+        .filter(move |c| c.is_element() && c.tag_name().name() == name)
+}
+
+/// Builds the defender of a battle from an XML spy report.
+pub fn parse_xml_spy_report(xml: &str) -> Result<EspionageReport, XmlReportError> {
+    let doc = Document::parse(xml)?;
+    let root = root(&doc, "spy_report")?;
+    let section = |name| children(root, name).next();
+
+    let mut missing = Vec::new();
+    let resources = match section("resources") {
+        Some(node) => Some(Resources::new(
+            number(node, "metal")?,
+            number(node, "crystal")?,
+            number(node, "deuterium")?,
+        )),
+        None => {
+            missing.push(ReportSection::Resources);
+            None
+        }
+    };
+
+    let mut units = HashMap::new();
+    let mut unrecognized = Vec::new();
+    for (name, report_section) in [
+        ("ships", ReportSection::Fleets),
+        ("defense", ReportSection::Defense),
+    ] {
+        let Some(node) = section(name) else {
+            missing.push(report_section);
+            continue;
+        };
+        for unit in children(node, "unit") {
+            let id: u16 = number(unit, "type")?;
+            let count: u64 = number(unit, "count")?;
+            match UnitType::from_tech_id(id) {
+                Some(ut) if ut.is_missile() => {}
+                Some(ut) => *units.entry(ut).or_insert(0) += count,
+                None => unrecognized.push(format!("id {id} {count}")),
+            }
+        }
+    }
+
+    let mut tech = TechLevels {
+        weapon: 0,
+        shield: 0,
+        armor: 0,
+    };
+    match section("research") {
+        Some(node) => {
+            for t in children(node, "tech") {
+                let level = number(t, "level")?;
+                match number(t, "type")? {
+                    WEAPONS_TECHNOLOGY => tech.weapon = level,
+                    SHIELDING_TECHNOLOGY => tech.shield = level,
+                    ARMOUR_TECHNOLOGY => tech.armor = level,
+                    _ => {}
+                }
+            }
+        }
+        None => missing.push(ReportSection::Research),
+    }
+
+    Ok(EspionageReport {
+        defender: Player {
+            fleet: Fleet { units },
+            tech,
+        },
+        resources,
+        missing,
+        unrecognized,
+    })
+}
+
+/// A battle as an XML combat report recorded it.
+#[derive(Debug, Clone)]
+pub struct CombatReport {
+    /// Participants as they entered the battle, with default universe
+    /// settings; the round limit is raised to `rounds` if the report
+    /// records a longer battle.
+    pub battle: Battle,
+    /// Surviving units per attacking participant, indexed by `UnitType`.
+    pub attackers_remaining: Vec<[u64; UnitType::count()]>,
+    /// Same for the defenders.
+    pub defenders_remaining: Vec<[u64; UnitType::count()]>,
+    /// Rounds fought, if the report says.
+    pub rounds: Option<usize>,
+}
+
+/// Research bonus in percent, 10 per level.
+fn research_level(node: Node, attribute: &'static str) -> Result<u8, XmlReportError> {
+    let percent: u16 = number(node, attribute)?;
+    Ok(u8::try_from(percent / 10).unwrap_or(u8::MAX))
+}
+
+fn participant(node: Node) -> Result<(Player, [u64; UnitType::count()]), XmlReportError> {
+    let mut units = HashMap::new();
+    let mut remaining = [0u64; UnitType::count()];
+    for unit in children(node, "unit") {
+        let id: u16 = number(unit, "type")?;
+        let ut = UnitType::from_tech_id(id).ok_or(XmlReportError::UnknownUnit(id))?;
+        *units.entry(ut).or_insert(0) += number::<u64>(unit, "count")?;
+        remaining[ut as usize] += number::<u64>(unit, "remaining")?;
+    }
+    let player = Player {
+        fleet: Fleet { units },
+        tech: TechLevels {
+            weapon: research_level(node, "weapons")?,
+            shield: research_level(node, "shields")?,
+            armor: research_level(node, "armor")?,
+        },
+    };
+    Ok((player, remaining))
+}
+
+fn side_total(participants: &[[u64; UnitType::count()]]) -> [u64; UnitType::count()] {
+    let mut total = [0u64; UnitType::count()];
+    for p in participants {
+        for (t, &n) in total.iter_mut().zip(p) {
+            *t += n;
+        }
+    }
+    total
+}
+
+impl CombatReport {
+    /// Outcome recorded by the report, by the same rule as
+    /// [`BattleReport::outcome`](crate::BattleReport::outcome).
+    pub fn outcome(&self) -> BattleOutcome {
+        let attacker_alive = self.attackers_remaining.iter().flatten().any(|&n| n > 0);
+        let defender_alive = self.defenders_remaining.iter().flatten().any(|&n| n > 0);
+        match (attacker_alive, defender_alive) {
+            (true, false) => BattleOutcome::AttackerWin,
+            (false, true) => BattleOutcome::DefenderWin,
+            _ => BattleOutcome::Draw,
+        }
+    }
+
+    /// Simulates the reported battle `config.trials` times and sets the
+    /// recorded result against the simulated distribution.
+    pub fn replay(&self, config: &MonteCarloConfig) -> ReplayComparison {
+        let statistics = simulate_battles_stats(&self.battle, config);
+        let outcome = self.outcome();
+        let outcome_probability = match outcome {
+            BattleOutcome::AttackerWin => statistics.attacker_win,
+            BattleOutcome::DefenderWin => statistics.defender_win,
+            BattleOutcome::Draw => statistics.draw,
+        };
+        let compare = |players: &[Player],
+                       actual: [u64; UnitType::count()],
+                       summaries: &[UnitSummary; UnitType::count()]| {
+            UnitType::iter()
+                .filter(|ut| players.iter().any(|p| p.fleet.units.get(ut) > Some(&0)))
+                .map(|&ut| UnitComparison {
+                    unit_type: ut,
+                    actual: actual[ut as usize],
+                    simulated: summaries[ut as usize].clone(),
+                })
+                .collect()
+        };
+        let rounds_probability = self.rounds.map(|n| {
+            let trials = statistics.rounds.get(n).copied().unwrap_or(0);
+            trials as f64 / statistics.trials.max(1) as f64
+        });
+        ReplayComparison {
+            outcome,
+            outcome_probability,
+            rounds: self.rounds,
+            rounds_probability,
+            attacker: compare(
+                &self.battle.attackers,
+                side_total(&self.attackers_remaining),
+                &statistics.attacker,
+            ),
+            defender: compare(
+                &self.battle.defenders,
+                side_total(&self.defenders_remaining),
+                &statistics.defender,
+            ),
+            statistics,
+        }
+    }
+}
+
+/// Reads an XML combat report.
+pub fn parse_xml_combat_report(xml: &str) -> Result<CombatReport, XmlReportError> {
+    let doc = Document::parse(xml)?;
+    let root = root(&doc, "combat_report")?;
+    let mut report = CombatReport {
+        battle: Battle::default(),
+        attackers_remaining: Vec::new(),
+        defenders_remaining: Vec::new(),
+        rounds: match root.attribute("rounds") {
+            Some(_) => Some(number(root, "rounds")?),
+            None => None,
+        },
+    };
+    for node in children(root, "attacker") {
+        let (player, remaining) = participant(node)?;
+        report.battle.attackers.push(player);
+        report.attackers_remaining.push(remaining);
+    }
+    for node in children(root, "defender") {
+        let (player, remaining) = participant(node)?;
+        report.battle.defenders.push(player);
+        report.defenders_remaining.push(remaining);
+    }
+    if report.battle.attackers.is_empty() {
+        return Err(XmlReportError::MissingSide("attacker"));
+    }
+    if report.battle.defenders.is_empty() {
+        return Err(XmlReportError::MissingSide("defender"));
+    }
+    let combat = &mut report.battle.universe.combat;
+    combat.rounds = combat.rounds.max(report.rounds.unwrap_or(0));
+    Ok(report)
+}
+
+/// A recorded battle next to its simulated distribution.
+#[derive(Debug, Clone)]
+pub struct ReplayComparison {
+    /// Outcome the report recorded.
+    pub outcome: BattleOutcome,
+    /// Share of simulated trials that ended the same way.
+    pub outcome_probability: f64,
+    /// Rounds the report recorded, if it says.
+    pub rounds: Option<usize>,
+    /// Share of simulated trials that lasted exactly `rounds` rounds.
+    pub rounds_probability: Option<f64>,
+    /// Survivors of each unit type the attacking side fielded.
+    pub attacker: Vec<UnitComparison>,
+    /// Same for the defending side.
+    pub defender: Vec<UnitComparison>,
+    pub statistics: BattleStatistics,
+}
+
+/// Recorded survivors of one unit type against the simulated ones, summed
+/// over a side's participants.
+#[derive(Debug, Clone)]
+pub struct UnitComparison {
+    pub unit_type: UnitType,
+    pub actual: u64,
+    pub simulated: UnitSummary,
+}
+
+impl UnitComparison {
+    /// Distance of the recorded count from the simulated mean, in standard
+    /// deviations; 0 when both agree on a count that never varied.
+    pub fn deviation(&self) -> f64 {
+        let diff = self.actual as f64 - self.simulated.mean;
+        if self.simulated.std_dev > 0.0 {
+            diff / self.simulated.std_dev
+        } else if diff == 0.0 {
+            0.0
+        } else {
+            diff.signum() * f64::INFINITY
+        }
+    }
+
+    /// `true` when the recorded count lies between the simulated minimum and
+    /// maximum.
+    pub fn within_simulated_range(&self) -> bool {
+        (self.simulated.min..=self.simulated.max).contains(&self.actual)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPY_REPORT: &str = include_str!("../../tests/fixtures/xml/spy-report.xml");
+    const PARTIAL_SPY_REPORT: &str =
+        include_str!("../../tests/fixtures/xml/spy-report-partial.xml");
+    const COMBAT_REPORT: &str = include_str!("../../tests/fixtures/xml/combat-report.xml");
+
+    #[test]
+    fn tech_ids_round_trip() {
+        for &ut in UnitType::iter() {
+            assert_eq!(UnitType::from_tech_id(ut.tech_id()), Some(ut));
+        }
+        assert_eq!(UnitType::from_tech_id(202), Some(UnitType::SmallCargo));
+        assert_eq!(UnitType::from_tech_id(204), Some(UnitType::LightFighter));
+        assert_eq!(UnitType::from_tech_id(401), Some(UnitType::MissileLauncher));
+        assert_eq!(UnitType::from_tech_id(219), Some(UnitType::PathFinder));
+        assert_eq!(UnitType::from_tech_id(109), None);
+    }
+
+    #[test]
+    fn reads_a_full_spy_report() {
+        let report = parse_xml_spy_report(SPY_REPORT).unwrap();
+        assert!(report.is_complete());
+        assert_eq!(
+            report.resources,
+            Some(Resources::new(1_234_567.0, 234_567.0, 45_678.0))
+        );
+        let units = &report.defender.fleet.units;
+        assert_eq!(units[&UnitType::SmallCargo], 40);
+        assert_eq!(units[&UnitType::LightFighter], 120);
+        assert_eq!(units[&UnitType::SolarSatellite], 25);
+        assert_eq!(units[&UnitType::MissileLauncher], 50);
+        assert_eq!(units[&UnitType::SmallShieldDome], 1);
+        let tech = &report.defender.tech;
+        assert_eq!((tech.weapon, tech.shield, tech.armor), (12, 11, 12));
+    }
+
+    #[test]
+    fn leaves_missiles_out_of_the_defender() {
+        let report = parse_xml_spy_report(SPY_REPORT).unwrap();
+        let units = &report.defender.fleet.units;
+        assert!(!units.contains_key(&UnitType::AntiBallisticMissiles));
+        assert!(!units.contains_key(&UnitType::InterplanetaryMissiles));
+        assert!(report.unrecognized.is_empty());
+    }
+
+    #[test]
+    fn reports_hidden_sections() {
+        let report = parse_xml_spy_report(PARTIAL_SPY_REPORT).unwrap();
+        assert_eq!(
+            report.missing,
+            [ReportSection::Defense, ReportSection::Research]
+        );
+        assert_eq!(report.defender.fleet.units[&UnitType::LargeCargo], 2);
+    }
+
+    #[test]
+    fn reads_a_combat_report() {
+        let report = parse_xml_combat_report(COMBAT_REPORT).unwrap();
+        assert_eq!(report.rounds, Some(2));
+        assert_eq!(report.battle.attackers.len(), 2);
+        assert_eq!(report.battle.defenders.len(), 1);
+        let second = &report.battle.attackers[1];
+        assert_eq!(
+            (second.tech.weapon, second.tech.shield, second.tech.armor),
+            (8, 9, 8)
+        );
+        assert_eq!(
+            report.attackers_remaining[0][UnitType::LightFighter as usize],
+            291
+        );
+        assert_eq!(report.outcome(), BattleOutcome::AttackerWin);
+    }
+
+    #[test]
+    fn replays_a_combat_report() {
+        let report = parse_xml_combat_report(COMBAT_REPORT).unwrap();
+        let config = MonteCarloConfig {
+            trials: 500,
+            seed: Some(1),
+            ..MonteCarloConfig::default()
+        };
+        let replay = report.replay(&config);
+        assert_eq!(replay.outcome, BattleOutcome::AttackerWin);
+        assert!(replay.outcome_probability > 0.9);
+        assert_eq!(replay.rounds, Some(2));
+        assert!(replay.rounds_probability.unwrap() > 0.0);
+        let fighters = replay
+            .attacker
+            .iter()
+            .find(|u| u.unit_type == UnitType::LightFighter)
+            .unwrap();
+        assert_eq!(fighters.actual, 291);
+        assert!(fighters.simulated.mean > 0.0);
+        assert_eq!(replay.defender.len(), 3);
+    }
+
+    #[test]
+    fn raises_the_round_limit_for_longer_battles() {
+        let xml = COMBAT_REPORT.replace(r#"rounds="2""#, r#"rounds="8""#);
+        let report = parse_xml_combat_report(&xml).unwrap();
+        assert_eq!(report.battle.universe.combat.rounds, 8);
+    }
+
+    #[test]
+    fn rejects_malformed_reports() {
+        assert!(matches!(
+            parse_xml_combat_report(SPY_REPORT),
+            Err(XmlReportError::WrongReport { .. })
+        ));
+        assert!(matches!(
+            parse_xml_combat_report(&COMBAT_REPORT.replace(r#"type="206""#, r#"type="299""#)),
+            Err(XmlReportError::UnknownUnit(299))
+        ));
+        assert!(matches!(
+            parse_xml_combat_report(&COMBAT_REPORT.replace(r#"rounds="2""#, r#"rounds="two""#)),
+            Err(XmlReportError::BadAttribute { .. })
+        ));
+        assert!(matches!(
+            parse_xml_spy_report("<spy_report>"),
+            Err(XmlReportError::Xml(_))
+        ));
+    }
+}
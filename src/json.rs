@@ -0,0 +1,258 @@
+//! JSON battle input and result format.
+//!
+//! # Input
+//!
+//! A [`SimulationInput`] document: the [`Battle`] fields at the top level,
+//! plus optional simulation settings.
+//!
+//! ```json
+//! {
+//!   "attackers": [
+//!     {
+//!       "fleet": { "LightFighter": 100, "Cruiser": 5 },
+//!       "tech": { "weapon": 12, "shield": 12, "armor": 12 }
+//!     }
+//!   ],
+//!   "defenders": [
+//!     {
+//!       "fleet": { "MissileLauncher": 50 },
+//!       "tech": { "weapon": 11, "shield": 11, "armor": 11 }
+//!     }
+//!   ],
+//!   "universe": {
+//!     "fleet_debris": 0.3,
+//!     "combat": { "rounds": 6, "rapid_fire": true }
+//!   },
+//!   "simulation": { "trials": 10000, "seed": 42 }
+//! }
+//! ```
+//!
+//! - `attackers`, `defenders` (required): one [`Player`](crate::Player) per
+//!   participant, more than one for alliance combat.
+//!   - `fleet`: unit type name to count. Names are the [`UnitType`] variant
+//!     names; case, spaces, `_` and `-` are ignored, so `"light_fighter"`
+//!     works too.
+//!   - `tech`: `weapon`, `shield` and `armor` research levels, all required.
+//! - `universe` (optional): [`UniverseSettings`](crate::UniverseSettings).
+//!   Every field is optional and defaults to the live game: `fleet_debris`,
+//!   `defense_debris`, `defense_rebuild`, `plunder`, `moon`
+//!   (`debris_per_percent`, `max_chance`) and `combat`
+//!   ([`CombatRules`](crate::CombatRules): `rounds`, `bounce_threshold`,
+//!   `explosion_threshold`, `rapid_fire`, `defenses_shoot`).
+//! - `simulation` (optional): [`MonteCarloConfig`]. Every field is optional:
+//!   `trials`, `seed` (omit or `null` for an OS seed), `percentiles`,
+//!   `threads`, `engine` (`"PerUnit"` or `"Grouped"`) and `rng`
+//!   (`"Xoshiro"`, `"WyRand"` or `"Counter"`).
+//!
+//! Unknown keys, at the top level as well as inside participants,
+//! `universe` and `simulation`, are rejected, so a misspelt setting is an error rather than a silent default.
+//!
+//! # Results
+//!
+//! A [`SimulationResult`], tagged by `kind`. Unit counts are objects from
+//! unit type name to count that leave out zero entries.
+//!
+//! A single battle (`"kind": "battle"`) is a [`BattleReport`] plus its
+//! `outcome` (`"AttackerWin"`, `"DefenderWin"` or `"Draw"`):
+//!
+//! ```json
+//! {
+//!   "kind": "battle",
+//!   "outcome": "AttackerWin",
+//!   "attackers": [{ "initial": { "LightFighter": 100 }, "remaining": { "LightFighter": 90 }, "rebuilt": {} }],
+//!   "defenders": [{ "initial": { "MissileLauncher": 50 }, "remaining": {}, "rebuilt": { "MissileLauncher": 27 } }],
+//!   "rounds": [],
+//!   "rounds_fought": 2,
+//!   "debris": { "metal": 9000.0, "crystal": 3000.0 },
+//!   "moon_chance": 0
+//! }
+//! ```
+//!
+//! `rounds` holds one entry per round (surviving `attackers` and `defenders`
+//! per participant, `attacker_fire` and `defender_fire` totals) when the
+//! battle was simulated in detail.
+//!
+//! Monte-Carlo statistics (`"kind": "statistics"`) are a
+//! [`BattleStatistics`]; `attacker` and `defender` map each unit type that
+//! survived at least one trial to its `mean`, `std_dev`, `min`, `max` and
+//! `percentiles` (`[percentile, count]` pairs).
+
+use crate::simulator::monte_carlo::MonteCarloConfig;
+use crate::simulator::report::{BattleOutcome, BattleReport};
+use crate::simulator::stats::BattleStatistics;
+use crate::types::battle::Battle;
+use crate::types::player::Player;
+use crate::types::unit_type::UnitType;
+use crate::types::universe::UniverseSettings;
+use serde::{Deserialize, Serialize};
+
+/// Top level of a JSON input document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "InputDocument")]
+pub struct SimulationInput {
+    #[serde(flatten)]
+    pub battle: Battle,
+    #[serde(default)]
+    pub simulation: MonteCarloConfig,
+}
+
+/// [`SimulationInput`] with the battle fields written out, so that unknown
+/// top-level keys are rejected; serde ignores them next to a flattened
+/// field.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputDocument {
+    attackers: Vec<Player>,
+    defenders: Vec<Player>,
+    #[serde(default)]
+    universe: UniverseSettings,
+    #[serde(default)]
+    simulation: MonteCarloConfig,
+}
+
+impl From<InputDocument> for SimulationInput {
+    fn from(doc: InputDocument) -> Self {
+        SimulationInput {
+            battle: Battle {
+                attackers: doc.attackers,
+                defenders: doc.defenders,
+                universe: doc.universe,
+            },
+            simulation: doc.simulation,
+        }
+    }
+}
+
+impl SimulationInput {
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Top level of a JSON result document.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SimulationResult<'a> {
+    Battle {
+        outcome: BattleOutcome,
+        #[serde(flatten)]
+        report: &'a BattleReport,
+    },
+    Statistics(&'a BattleStatistics),
+}
+
+impl<'a> SimulationResult<'a> {
+    pub fn battle(report: &'a BattleReport) -> Self {
+        SimulationResult::Battle {
+            outcome: report.outcome(),
+            report,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("results always serialize")
+    }
+}
+
+/// Serializers for per-type arrays as objects keyed by unit type name.
+pub(crate) mod unit_counts {
+    use super::UnitType;
+    use crate::simulator::stats::UnitSummary;
+    use serde::{Serialize, Serializer};
+
+    struct Counts<'a>(&'a [u64; UnitType::count()]);
+
+    impl Serialize for Counts<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(UnitType::iter().zip(self.0).filter(|(_, n)| **n > 0))
+        }
+    }
+
+    pub(crate) fn serialize<S: Serializer>(
+        counts: &[u64; UnitType::count()],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Counts(counts).serialize(serializer)
+    }
+
+    /// One object per participant.
+    pub(crate) fn serialize_each<S: Serializer>(
+        participants: &[[u64; UnitType::count()]],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(participants.iter().map(Counts))
+    }
+
+    pub(crate) fn serialize_means<S: Serializer>(
+        means: &[f64; UnitType::count()],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(UnitType::iter().zip(means).filter(|(_, m)| **m > 0.0))
+    }
+
+    /// Leaves out types that never survived a trial.
+    pub(crate) fn serialize_summaries<S: Serializer>(
+        summaries: &[UnitSummary; UnitType::count()],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(UnitType::iter().zip(summaries).filter(|(_, s)| s.max > 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINIMAL: &str = r#"{
+        "attackers": [{ "fleet": { "LightFighter": 10 }, "tech": { "weapon": 1, "shield": 2, "armor": 3 } }],
+        "defenders": [{ "fleet": { "missile_launcher": 5 }, "tech": { "weapon": 0, "shield": 0, "armor": 0 } }]
+    }"#;
+
+    fn with(extra: &str) -> String {
+        MINIMAL.trim_end().trim_end_matches('}').to_owned() + "," + extra + "}"
+    }
+
+    #[test]
+    fn reads_a_minimal_document() {
+        let input = SimulationInput::from_json(MINIMAL).unwrap();
+        assert_eq!(input.battle.attackers[0].tech.armor, 3);
+        assert_eq!(
+            input.battle.defenders[0].fleet.units[&UnitType::MissileLauncher],
+            5
+        );
+        assert_eq!(input.battle.universe.combat.rounds, 6);
+        assert_eq!(input.simulation.trials, 1000);
+    }
+
+    #[test]
+    fn reads_partial_settings() {
+        let json =
+            with(r#""universe": { "combat": { "rounds": 2 } }, "simulation": { "seed": 9 }"#);
+        let input = SimulationInput::from_json(&json).unwrap();
+        assert_eq!(input.battle.universe.combat.rounds, 2);
+        assert!(input.battle.universe.combat.rapid_fire);
+        assert_eq!(input.simulation.seed, Some(9));
+    }
+
+    #[test]
+    fn rejects_misspelt_keys() {
+        for extra in [
+            r#""universe": { "combat": { "round": 2 } }"#,
+            r#""universe": { "moon": { "max": 20 } }"#,
+            r#""universe": { "plunderr": 0.75 }"#,
+            r#""simulation": { "trial": 10 }"#,
+            r#""simulaton": { "trials": 0 }"#,
+            r#""universe": { }, "defender": []"#,
+        ] {
+            let err = SimulationInput::from_json(&with(extra)).unwrap_err();
+            assert!(err.to_string().contains("unknown field"), "{extra}: {err}");
+        }
+        let typo_in_tech = MINIMAL.replace(r#""armor": 3"#, r#""armour": 3"#);
+        assert!(SimulationInput::from_json(&typo_in_tech).is_err());
+        let typo_in_player = MINIMAL.replace(
+            r#""fleet": { "LightFighter""#,
+            r#""fleets": { "LightFighter""#,
+        );
+        assert!(SimulationInput::from_json(&typo_in_player).is_err());
+    }
+}
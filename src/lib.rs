@@ -0,0 +1,46 @@
+//! OGame battle simulator.
+//!
+//! The crate exposes the battle input types ([`Battle`], [`Player`],
+//! [`Fleet`], [`TechLevels`], [`UnitType`]), the simulator entry points
+//! ([`simulate_battle`], [`simulate_battles_avg`]) and the static stat tables
+//! they are built on ([`UNIT_STATS`], [`RAPID_FIRE`]). Battles and results
+//! can be read and written as JSON, see [`json`]; defenders can be built
+//! from espionage reports ([`parse_espionage_report`]) and results rendered
+//! like in-game combat reports ([`combat_report_text`]).
+//!
+//! Apart from [`json`], everything is exported from the crate root and the
+//! modules behind it are private.
+
+mod ingame;
+pub mod json;
+mod simulator;
+#[cfg(test)]
+mod test_util;
+mod types;
+mod utils;
+
+pub use ingame::{
+    CombatReport, EspionageReport, NotAnEspionageReport, ReplayComparison, ReportSection,
+    UnitComparison, XmlReportError, combat_report_html, combat_report_text, parse_espionage_report,
+    parse_xml_combat_report, parse_xml_spy_report,
+};
+pub use json::{SimulationInput, SimulationResult};
+pub use simulator::{
+    AdaptiveStatistics, BattleOutcome, BattleReport, BattleStatistics, ConvergenceCriteria,
+    ConvergenceMetric, DebrisField, Engine, FireStats, Loot, MonteCarloConfig, ParticipantReport,
+    ProfitReport, RaidPlan, RngBackend, RoundReport, TradeRatios, UnitSummary, analyze_raid,
+    cargo_capacity, plunder, resource_value, simulate_acs_battle, simulate_acs_battle_detailed,
+    simulate_battle, simulate_battle_detailed, simulate_battle_seeded, simulate_battle_with_rng,
+    simulate_battles_adaptive, simulate_battles_avg, simulate_battles_avg_seeded,
+    simulate_battles_stats, simulate_grouped_battle, simulate_grouped_battle_detailed,
+};
+pub use types::battle::Battle;
+pub use types::player::{Fleet, Player, TechLevels};
+pub use types::resources::Resources;
+pub use types::unit_type::{UnitType, UnknownUnitType};
+pub use types::universe::{CombatRules, MoonRules, UniverseSettings};
+pub use types::{RAPID_FIRE, UNIT_STATS, UnitStats, rapid_fire_for, stats_for};
+pub use utils::counter_rng::CounterRng;
+pub use utils::fast_rng::{FastRng, derive_seed};
+pub use utils::rng::BattleRng;
+pub use utils::wyrand::WyRand;
@@ -1,74 +1,300 @@
-mod simulator;
-mod types;
-mod utils; // This line links to simulator/mod.rs
+mod cli;
 
-use simulator::simulate_battle;
-use simulator::simulate_battles_avg;
-use std::collections::HashMap;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use cli::CliError;
+use cli::input::{
+    TechSpec, check_battle_size, parse_participant, read_battle_file, read_combat_report,
+    read_json_file, read_spy_report,
+};
+use cli::output::{self, Format};
+use ogame_battle_sim::{
+    Battle, CombatRules, Engine, FastRng, MonteCarloConfig, RngBackend, SimulationInput,
+    simulate_acs_battle, simulate_acs_battle_detailed, simulate_battles_stats,
+    simulate_grouped_battle, simulate_grouped_battle_detailed,
+};
+use std::path::PathBuf;
+use std::process::ExitCode;
 use std::time::Instant;
-use types::player::{Fleet, Player, TechLevels};
-use types::unit_type::UnitType;
 
-fn main() {
-    // Define attacker fleet
-    let mut attacker_units = HashMap::new();
-    attacker_units.insert(UnitType::LightFighter, 100);
-    // attacker_units.insert(UnitType::Cruiser, 2);
+/// OGame battle simulator.
+///
+/// Exits with 0 on success, 2 on invalid arguments or battle input and 1 when
+/// a file cannot be read.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-    // Define defender fleet
-    let mut defender_units = HashMap::new();
-    defender_units.insert(UnitType::MissileLauncher, 50);
-    // defender_units.insert(UnitType::PlasmaTurret, 1);
+#[derive(Subcommand)]
+enum Command {
+    /// Simulate a single battle and print what is left of each fleet.
+    Battle {
+        #[command(flatten)]
+        input: BattleInput,
+        /// Seed of the battle; drawn from the OS when omitted.
+        #[arg(long)]
+        seed: Option<u64>,
+        #[arg(long, value_enum)]
+        engine: Option<EngineArg>,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Simulate many battles and print outcome statistics.
+    Stats {
+        #[command(flatten)]
+        input: BattleInput,
+        #[command(flatten)]
+        run: RunArgs,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Re-simulate a combat report in the simulator's XML layout and compare
+    /// the recorded result with the simulated ones.
+    Replay {
+        /// Combat report as XML.
+        report: PathBuf,
+        #[command(flatten)]
+        run: RunArgs,
+    },
+    /// Time a Monte-Carlo run of the given battle.
+    Bench {
+        #[command(flatten)]
+        input: BattleInput,
+        #[command(flatten)]
+        run: RunArgs,
+    },
+}
 
-    // Define technology levels
-    let attacker_tech = TechLevels {
-        weapon: 12,
-        shield: 12,
-        armor: 12,
-    };
+/// Who fights, given inline, in a battle file or in a JSON document.
+#[derive(Args)]
+struct BattleInput {
+    /// Attacking participant as `Type=count,...[@weapon/shield/armor]`;
+    /// repeat for an ACS attack.
+    #[arg(short, long = "attacker", value_name = "FLEET")]
+    attackers: Vec<String>,
+    /// Defending participant, same syntax; repeat for ACS defend.
+    #[arg(short, long = "defender", value_name = "FLEET")]
+    defenders: Vec<String>,
+    /// Research of attackers given without `@...`.
+    #[arg(long, value_name = "W/S/A", default_value = "0/0/0")]
+    attacker_tech: TechSpec,
+    /// Research of defenders given without `@...`.
+    #[arg(long, value_name = "W/S/A", default_value = "0/0/0")]
+    defender_tech: TechSpec,
+    /// Battle file with one `attacker ...` or `defender ...` line per
+    /// participant, instead of `--attacker`/`--defender`.
+    #[arg(short, long, conflicts_with_all = ["attackers", "defenders"])]
+    file: Option<PathBuf>,
+    /// JSON battle document (see the `json` module of the library), whose
+    /// `simulation` settings apply unless overridden by flags.
+    #[arg(long, conflicts_with_all = ["attackers", "defenders", "file"])]
+    json: Option<PathBuf>,
+    /// Espionage report as the defender: English text copied out of the
+    /// game, or a spy report in the simulator's XML layout.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["defenders", "file", "json"])]
+    spy_report: Option<PathBuf>,
+    /// Combat rules; defaults to the live game, or to the JSON document's.
+    #[arg(long, value_enum)]
+    rules: Option<RulesArg>,
+}
 
-    let defender_tech = TechLevels {
-        weapon: 12,
-        shield: 12,
-        armor: 12,
-    };
+/// Monte-Carlo settings; unset ones come from the JSON document, if any,
+/// or the library defaults.
+#[derive(Args)]
+struct RunArgs {
+    /// Number of battles [default: 1000]
+    #[arg(short = 'n', long)]
+    trials: Option<usize>,
+    /// Master seed; drawn from the OS when omitted.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Worker threads; 0 uses every available core [default: 0]
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+    /// [default: per-unit]
+    #[arg(long, value_enum)]
+    engine: Option<EngineArg>,
+    /// [default: xoshiro]
+    #[arg(long, value_enum)]
+    rng: Option<RngArg>,
+}
 
-    // Build players
-    let attacker = Player {
-        fleet: Fleet {
-            units: attacker_units,
-        },
-        tech: attacker_tech,
-    };
+#[derive(Clone, Copy, ValueEnum)]
+enum EngineArg {
+    PerUnit,
+    Grouped,
+}
 
-    let defender = Player {
-        fleet: Fleet {
-            units: defender_units,
-        },
-        tech: defender_tech,
-    };
+#[derive(Clone, Copy, ValueEnum)]
+enum RngArg {
+    Xoshiro,
+    Wyrand,
+    Counter,
+}
 
-    // Simulate the battle
-    // simulate_battle(&attacker, &defender);
-    let start = Instant::now();
-    let result = simulate_battles_avg(&attacker, &defender, 100);
-    let elapsed = start.elapsed();
-    println!("10B FastRng::next() calls took: {:?}", elapsed);
-    println!("{:?}", result);
-    ////////////
-    // let mut rng = FastRng::new(None);
-    // println!("{}", rng.next(10_000_000_000));
+#[derive(Clone, Copy, ValueEnum)]
+enum RulesArg {
+    /// The current live game.
+    Live,
+    /// Live rules without rapid fire.
+    NoRapidFire,
+    /// Live rules with defenses that never fire back.
+    DefensesDontShoot,
+}
 
-    // let range_end = 10_000_000_000u64;
-    // let count = 10_000_000_000u64;
+impl From<EngineArg> for Engine {
+    fn from(e: EngineArg) -> Self {
+        match e {
+            EngineArg::PerUnit => Engine::PerUnit,
+            EngineArg::Grouped => Engine::Grouped,
+        }
+    }
+}
 
-    // let start = Instant::now();
+impl From<RngArg> for RngBackend {
+    fn from(r: RngArg) -> Self {
+        match r {
+            RngArg::Xoshiro => RngBackend::Xoshiro,
+            RngArg::Wyrand => RngBackend::WyRand,
+            RngArg::Counter => RngBackend::Counter,
+        }
+    }
+}
+
+impl BattleInput {
+    fn load(&self) -> Result<SimulationInput, CliError> {
+        let mut input = if let Some(path) = &self.json {
+            read_json_file(path)?
+        } else {
+            SimulationInput {
+                battle: self.battle()?,
+                simulation: MonteCarloConfig::default(),
+            }
+        };
+        let battle = &mut input.battle;
+        if battle.attackers.is_empty() {
+            return Err(CliError::Input("no attacking participant given".into()));
+        }
+        if battle.defenders.is_empty() {
+            return Err(CliError::Input("no defending participant given".into()));
+        }
+        match self.rules {
+            Some(RulesArg::Live) => battle.universe.combat = CombatRules::live(),
+            Some(RulesArg::NoRapidFire) => battle.universe.combat = CombatRules::no_rapid_fire(),
+            Some(RulesArg::DefensesDontShoot) => {
+                battle.universe.combat = CombatRules::defenses_dont_shoot()
+            }
+            None => {}
+        }
+        Ok(input)
+    }
+
+    fn battle(&self) -> Result<Battle, CliError> {
+        match &self.file {
+            Some(path) => read_battle_file(path, self.attacker_tech, self.defender_tech),
+            None => Ok(Battle {
+                attackers: self
+                    .attackers
+                    .iter()
+                    .map(|spec| parse_participant(spec, self.attacker_tech))
+                    .collect::<Result<_, _>>()?,
+                defenders: match &self.spy_report {
+                    Some(path) => vec![read_spy_report(path)?],
+                    None => self
+                        .defenders
+                        .iter()
+                        .map(|spec| parse_participant(spec, self.defender_tech))
+                        .collect::<Result<_, _>>()?,
+                },
+                ..Battle::default()
+            }),
+        }
+    }
+}
 
-    // for _ in 0..count {
-    //     // Prevent optimizer from removing this
-    //     black_box(rng.next(range_end));
-    // }
+impl RunArgs {
+    /// Applies the flags on top of `config`.
+    fn config(&self, mut config: MonteCarloConfig) -> Result<MonteCarloConfig, CliError> {
+        config.trials = self.trials.unwrap_or(config.trials);
+        config.seed = self.seed.or(config.seed);
+        config.threads = self.threads.unwrap_or(config.threads);
+        config.engine = self.engine.map_or(config.engine, Engine::from);
+        config.rng = self.rng.map_or(config.rng, RngBackend::from);
+        if config.trials == 0 {
+            return Err(CliError::Input(
+                "the number of trials must be at least 1".into(),
+            ));
+        }
+        Ok(config)
+    }
+}
+
+fn run(cli: Cli) -> Result<(), CliError> {
+    match cli.command {
+        Command::Battle {
+            input,
+            seed,
+            engine,
+            format,
+        } => {
+            let SimulationInput { battle, simulation } = input.load()?;
+            let mut rng = FastRng::new(seed.or(simulation.seed));
+            let detailed = format.is_combat_report();
+            let engine = engine.map_or(simulation.engine, Engine::from);
+            check_battle_size(&battle, engine)?;
+            let report = match (engine, detailed) {
+                (Engine::PerUnit, false) => simulate_acs_battle(&battle, &mut rng),
+                (Engine::PerUnit, true) => simulate_acs_battle_detailed(&battle, &mut rng),
+                (Engine::Grouped, false) => simulate_grouped_battle(&battle, &mut rng),
+                (Engine::Grouped, true) => simulate_grouped_battle_detailed(&battle, &mut rng),
+            };
+            print!("{}", output::battle_report(&battle, &report, format));
+        }
+        Command::Stats { input, run, format } => {
+            if format.is_combat_report() {
+                return Err(CliError::Input(
+                    "combat report formats only apply to `battle`".into(),
+                ));
+            }
+            let SimulationInput { battle, simulation } = input.load()?;
+            let config = run.config(simulation)?;
+            check_battle_size(&battle, config.engine)?;
+            let stats = simulate_battles_stats(&battle, &config);
+            print!("{}", output::statistics(&battle, &stats, format));
+        }
+        Command::Replay { report, run } => {
+            let report = read_combat_report(&report)?;
+            let config = run.config(MonteCarloConfig::default())?;
+            check_battle_size(&report.battle, config.engine)?;
+            let comparison = report.replay(&config);
+            print!("{}", output::replay(&comparison));
+        }
+        Command::Bench { input, run } => {
+            let SimulationInput { battle, simulation } = input.load()?;
+            let config = run.config(simulation)?;
+            check_battle_size(&battle, config.engine)?;
+            let start = Instant::now();
+            let stats = simulate_battles_stats(&battle, &config);
+            let elapsed = start.elapsed();
+            println!(
+                "{} trials in {:?} ({:.0} trials/s)",
+                stats.trials,
+                elapsed,
+                stats.trials as f64 / elapsed.as_secs_f64()
+            );
+        }
+    }
+    Ok(())
+}
 
-    // let elapsed = start.elapsed();
-    // println!("10B FastRng::next() calls took: {:?}", elapsed);
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            e.exit_code()
+        }
+    }
 }
@@ -0,0 +1,223 @@
+use crate::simulator::monte_carlo::{BLOCK_TRIALS, MonteCarloConfig, TrialAccumulator, run_trials};
+use crate::simulator::stats::{BattleStatistics, StatsAccumulator};
+use crate::types::battle::Battle;
+use crate::types::unit_type::UnitType;
+use std::time::{Duration, Instant};
+
+/// Quantity whose confidence interval decides when an adaptive run stops.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConvergenceMetric {
+    AttackerWinProbability,
+    /// Expected resources (metal + crystal + deuterium) lost by the attacker.
+    AttackerLosses,
+    /// Expected resources lost by the defender, rebuilt defenses excluded.
+    DefenderLosses,
+    AttackerSurvivors(UnitType),
+    DefenderSurvivors(UnitType),
+}
+
+/// When to stop simulating.
+#[derive(Debug, Clone)]
+pub struct ConvergenceCriteria {
+    /// Each metric with the confidence-interval half-width it must reach,
+    /// in the metric's own unit. The run stops once all of them are met.
+    pub targets: Vec<(ConvergenceMetric, f64)>,
+    /// Width of the interval in standard errors (1.96 ≈ 95 %).
+    pub z: f64,
+    /// Trials to run before the first check.
+    pub min_trials: usize,
+    /// Wall-clock budget; checked between batches.
+    pub time_budget: Option<Duration>,
+}
+
+impl Default for ConvergenceCriteria {
+    fn default() -> Self {
+        Self {
+            targets: vec![(ConvergenceMetric::AttackerWinProbability, 0.01)],
+            z: 1.96,
+            min_trials: 1000,
+            time_budget: None,
+        }
+    }
+}
+
+/// Result of [`simulate_battles_adaptive`].
+#[derive(Debug, Clone)]
+pub struct AdaptiveStatistics {
+    pub statistics: BattleStatistics,
+    /// `true` when every target was met before a budget ran out.
+    pub converged: bool,
+    /// Achieved half-width of each target metric, in input order.
+    pub precision: Vec<(ConvergenceMetric, f64)>,
+}
+
+/// Half-width of the Wilson score interval of a proportion. Unlike the
+/// normal approximation it stays positive when every trial so far went the
+/// same way.
+fn wilson_half_width(successes: u64, trials: u64, z: f64) -> f64 {
+    if trials == 0 {
+        return f64::INFINITY;
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = z * z;
+    z / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt()
+}
+
+impl StatsAccumulator {
+    fn half_width(&self, metric: ConvergenceMetric, z: f64) -> f64 {
+        match metric {
+            ConvergenceMetric::AttackerWinProbability => {
+                wilson_half_width(self.attacker_wins, self.trials, z)
+            }
+            ConvergenceMetric::AttackerLosses => self.attacker_losses.half_width(z),
+            ConvergenceMetric::DefenderLosses => self.defender_losses.half_width(z),
+            ConvergenceMetric::AttackerSurvivors(ut) => {
+                self.attacker[ut as usize].moments.half_width(z)
+            }
+            ConvergenceMetric::DefenderSurvivors(ut) => {
+                self.defender[ut as usize].moments.half_width(z)
+            }
+        }
+    }
+
+    /// Trials the slowest-converging target still seems to need in total.
+    fn trials_needed(&self, criteria: &ConvergenceCriteria) -> usize {
+        criteria
+            .targets
+            .iter()
+            .map(|&(metric, tolerance)| {
+                let ratio = self.half_width(metric, criteria.z) / tolerance;
+                (self.trials as f64 * ratio * ratio)
+                    .ceil()
+                    .min(usize::MAX as f64) as usize
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Keeps simulating until every target in `criteria` is met, `config.trials`
+/// trials have been run, or the time budget is spent.
+///
+/// Trials are seeded exactly as in [`simulate_battles_stats`], so an adaptive
+/// run that stopped after `n` trials matches a fixed run of `n` trials.
+///
+/// [`simulate_battles_stats`]: crate::simulator::simulate_battles_stats
+pub fn simulate_battles_adaptive(
+    battle: &Battle,
+    config: &MonteCarloConfig,
+    criteria: &ConvergenceCriteria,
+) -> AdaptiveStatistics {
+    let started = Instant::now();
+    let seed = config.master_seed();
+    let budget = config.trials;
+    let mut acc = StatsAccumulator::default();
+    let mut done = 0;
+    let mut next = usize::min(criteria.min_trials.max(1), budget);
+
+    loop {
+        acc.merge(run_trials(
+            battle,
+            done..next,
+            seed,
+            config.threads,
+            config.engine,
+            config.rng,
+            StatsAccumulator::default,
+        ));
+        done = next;
+
+        let needed = acc.trials_needed(criteria);
+        let out_of_time = criteria
+            .time_budget
+            .is_some_and(|limit| started.elapsed() >= limit);
+        if needed <= done || done >= budget || out_of_time {
+            break;
+        }
+        // aim for the estimate, but never more than double per batch and
+        // never less than one block more
+        next = needed.min(2 * done).max(done + BLOCK_TRIALS).min(budget);
+    }
+
+    let precision: Vec<_> = criteria
+        .targets
+        .iter()
+        .map(|&(metric, _)| (metric, acc.half_width(metric, criteria.z)))
+        .collect();
+    AdaptiveStatistics {
+        statistics: acc.finish(&config.percentiles),
+        converged: criteria
+            .targets
+            .iter()
+            .zip(&precision)
+            .all(|(&(_, tolerance), &(_, achieved))| achieved <= tolerance),
+        precision,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::battle;
+
+    #[test]
+    fn grows_past_a_small_first_batch() {
+        let battle = battle(
+            &[(UnitType::LightFighter, 20)],
+            &[(UnitType::MissileLauncher, 20)],
+        );
+        let config = MonteCarloConfig {
+            trials: 5000,
+            seed: Some(7),
+            threads: 1,
+            ..MonteCarloConfig::default()
+        };
+        let criteria = ConvergenceCriteria {
+            min_trials: 10,
+            ..ConvergenceCriteria::default()
+        };
+        let result = simulate_battles_adaptive(&battle, &config, &criteria);
+        assert!(result.statistics.trials > 10);
+    }
+
+    #[test]
+    fn one_sided_battle_still_needs_trials() {
+        // the attacker always wins, the normal approximation would claim a
+        // zero-width interval after the first batch
+        let battle = battle(
+            &[(UnitType::Cruiser, 100)],
+            &[(UnitType::MissileLauncher, 1)],
+        );
+        let config = MonteCarloConfig {
+            trials: 5000,
+            seed: Some(7),
+            threads: 1,
+            ..MonteCarloConfig::default()
+        };
+        let criteria = ConvergenceCriteria {
+            min_trials: 10,
+            ..ConvergenceCriteria::default()
+        };
+        let result = simulate_battles_adaptive(&battle, &config, &criteria);
+        assert!(result.statistics.attacker_win > 1.0 - 1e-9);
+        assert!(
+            result.statistics.trials >= 190,
+            "{}",
+            result.statistics.trials
+        );
+        assert!(result.converged);
+        assert!(result.precision[0].1 > 0.0);
+    }
+
+    #[test]
+    fn wilson_interval_matches_reference_values() {
+        // 0 of 10: Wilson 95 % interval is [0, 0.2775]
+        let hw = wilson_half_width(0, 10, 1.96);
+        let center = (0.0 + 1.96 * 1.96 / 20.0) / (1.0 + 1.96 * 1.96 / 10.0);
+        assert!((center + hw - 0.2775).abs() < 1e-3);
+        // large balanced sample: close to the normal approximation
+        let hw = wilson_half_width(5000, 10_000, 1.96);
+        assert!((hw - 1.96 * 0.005).abs() < 1e-5);
+    }
+}
@@ -1,203 +1,701 @@
+use crate::simulator::context::{BattleContext, Kind, combat_units};
+use crate::simulator::debris::DebrisField;
+use crate::simulator::monte_carlo::{Engine, RngBackend, TrialAccumulator, run_trials};
+use crate::simulator::report::{BattleReport, FireStats, ParticipantReport, RoundReport};
+use crate::types::battle::Battle;
 use crate::types::player::Player;
-use crate::types::rapid_fire_for;
-use crate::types::unit::Unit;
-use crate::types::unit_type::{UnitType, units_map_to_array};
+use crate::types::unit_type::UnitType;
 use crate::utils::fast_rng::FastRng;
+use crate::utils::rng::BattleRng;
 use std::iter;
 
+/// Simulates one battle with an RNG seeded from the OS.
 pub fn simulate_battle(
     attacker: &Player,
     defender: &Player,
 ) -> ([u64; UnitType::count()], [u64; UnitType::count()]) {
-    // println!("Simulating battle...");
-    let mut rng = FastRng::new(None);
-    let attacker_fleet = &attacker.fleet;
-    let defender_fleet = &defender.fleet;
-    let attacker_units = &attacker_fleet.units;
-    let defender_units = &defender_fleet.units;
-
-    let mut n_attacker_units = attacker_units.values().copied().sum::<u64>();
-    let mut n_defender_units = defender_units.values().copied().sum::<u64>();
-
-    // 2) Pre‐allocate the vectors
-    let mut attackers = Vec::with_capacity(n_attacker_units as usize);
-    let mut defenders = Vec::with_capacity(n_defender_units as usize);
-    // println!("{:?}", attackers);
-    // 3) Fill by cloning a small prototype for each type
-    //    This does ONE Unit::new per unit‐type, then repeats it `count` times cheaply.
-    for (&ut, &count) in attacker_units {
-        let proto = Unit::new(ut, &attacker.tech);
-        attackers.extend(iter::repeat(proto).take(count as usize));
-    }
-    for (&ut, &count) in defender_units {
-        let proto = Unit::new(ut, &defender.tech);
-        defenders.extend(iter::repeat(proto).take(count as usize));
-    }
-    let mut attacker_units_tracker = units_map_to_array(&attacker_units);
-    let mut defender_units_tracker = units_map_to_array(&defender_units);
-
-    // println!("Battle result: {:?}", attacker_units_tracker);
-
-    for _round in 0..6 {
-        // iterate attacker units tracker
-        let attacker_units_tracker_clone = attacker_units_tracker.clone();
-        let defender_units_tracker_clone = defender_units_tracker.clone();
-        let mut tmp_n_defender_units = n_defender_units;
-        let mut tmp_n_attacker_units = n_attacker_units;
-        // ─── Attacker units attack ───────────────────────────────────────
-        for (unit_type_index, &count) in attacker_units_tracker_clone.iter().enumerate() {
-            let attacker_unit_type: UnitType = UnitType::ALL[unit_type_index];
-            let dmg = Unit::attack_pts(attacker_unit_type, &attacker.tech);
-            for _ in 0..count {
-                let mut keep_firing = true; // rapid fire loop
-                while keep_firing && tmp_n_defender_units > 0 {
-                    // Find a defender unit that can be hit
-                    let defender_index = {
-                        // rng.next takes a u64, so cast the bound up
-                        let bound = tmp_n_defender_units as u64;
-                        rng.next(bound) as usize
-                    };
-
-                    let defender_unit = &mut defenders[defender_index];
-                    let defender_max_shield =
-                        Unit::shield_pts(defender_unit.unit_type, &defender.tech);
-                    if defender_unit.shield > 0.0 {
-                        // 1 % “bounce” rule (≤ 1 % of full shield does nothing)
-                        let pct = dmg / defender_max_shield * 100.0;
-                        if pct <= 1.0 {
-                            break; // skip this hit;
-                        }
-                        let overflow_damage = f64::max(0.0, dmg - defender_unit.shield);
-                        defender_unit.shield = f64::max(0.0, defender_unit.shield - dmg);
-                        defender_unit.hull = defender_unit.hull - overflow_damage;
-                    } else {
-                        defender_unit.hull = defender_unit.hull - dmg;
-                    }
-                    if defender_unit.hull <= 0.0 {
-                        // kill the defender unit
-                        defender_units_tracker[defender_unit.unit_type as usize] -= 1;
-                        defenders
-                            .swap(defender_index as usize, (tmp_n_defender_units - 1) as usize);
-                        tmp_n_defender_units -= 1;
-                        break;
-                    }
-                    let max_hull = Unit::hull_pts(defender_unit.unit_type, &defender.tech);
-                    if defender_unit.hull < 0.7 * max_hull {
-                        let explode_chance = 1.0 - (defender_unit.hull / max_hull);
-                        if rng.next_f64() < explode_chance {
-                            // kill the defender unit
-                            defender_units_tracker[defender_unit.unit_type as usize] -= 1;
-                            defenders
-                                .swap(defender_index as usize, (tmp_n_defender_units - 1) as usize);
-                            tmp_n_defender_units -= 1;
-                            break;
-                        }
-                    }
+    simulate_battle_with_rng(attacker, defender, &mut FastRng::new(None))
+}
 
-                    let rf = rapid_fire_for(attacker_unit_type, defender_unit.unit_type);
-                    keep_firing = rf > 1 && rng.next_f32() < (rf as f32 - 1.0) / rf as f32;
-                }
-            }
+/// Simulates one battle; the same inputs and seed always give the same result.
+pub fn simulate_battle_seeded(
+    attacker: &Player,
+    defender: &Player,
+    seed: u64,
+) -> ([u64; UnitType::count()], [u64; UnitType::count()]) {
+    simulate_battle_with_rng(attacker, defender, &mut FastRng::new(Some(seed)))
+}
+
+/// Simulates one battle drawing every random decision from `rng`.
+pub fn simulate_battle_with_rng<R: BattleRng>(
+    attacker: &Player,
+    defender: &Player,
+    rng: &mut R,
+) -> ([u64; UnitType::count()], [u64; UnitType::count()]) {
+    let battle = Battle::new(attacker.clone(), defender.clone());
+    let report = run_battle(&battle, rng, false);
+    (report.attacker_remaining(), report.defender_remaining())
+}
+
+/// Simulates one battle and records every round for the combat report.
+pub fn simulate_battle_detailed<R: BattleRng>(
+    attacker: &Player,
+    defender: &Player,
+    rng: &mut R,
+) -> BattleReport {
+    let battle = Battle::new(attacker.clone(), defender.clone());
+    run_battle(&battle, rng, true)
+}
+
+/// Simulates an alliance battle; losses are reported per participant.
+pub fn simulate_acs_battle<R: BattleRng>(battle: &Battle, rng: &mut R) -> BattleReport {
+    run_battle(battle, rng, false)
+}
+
+/// Like [`simulate_acs_battle`], recording every round for the combat report.
+pub fn simulate_acs_battle_detailed<R: BattleRng>(battle: &Battle, rng: &mut R) -> BattleReport {
+    run_battle(battle, rng, true)
+}
+
+/// The units of one side as parallel arrays, so that the per-round passes
+/// (shield reset, dropping the destroyed) run over contiguous memory.
+///
+/// Units `0..live` are alive; a destroyed unit is swapped behind them and
+/// cut off at the end of the round.
+struct CombatState<'a> {
+    hull: Vec<f64>,
+    shield: Vec<f64>,
+    /// Index into `kinds`.
+    kind: Vec<u32>,
+    kinds: &'a [Kind],
+    live: usize,
+    /// Surviving units per participant and type.
+    tracker: Vec<[u64; UnitType::count()]>,
+}
+
+impl<'a> CombatState<'a> {
+    /// Lays out the units of one side in the order of `kinds`, so that a
+    /// seed maps to exactly one battle.
+    fn deploy(players: &[Player], kinds: &'a [Kind]) -> Self {
+        let tracker: Vec<_> = players.iter().map(combat_units).collect();
+        let n_units = tracker.iter().flatten().sum::<u64>() as usize;
+        let mut state = Self {
+            hull: Vec::with_capacity(n_units),
+            shield: Vec::with_capacity(n_units),
+            kind: Vec::with_capacity(n_units),
+            kinds,
+            live: n_units,
+            tracker,
+        };
+        for (index, kind) in kinds.iter().enumerate() {
+            let count = state.tracker[kind.owner as usize][kind.unit_type as usize] as usize;
+            state.hull.extend(iter::repeat_n(kind.hull, count));
+            state.shield.extend(iter::repeat_n(kind.shield, count));
+            state.kind.extend(iter::repeat_n(index as u32, count));
         }
+        state
+    }
 
-        // ─── Defender units attack ───────────────────────────────────────
-        for (unit_type_index, &count) in defender_units_tracker_clone.iter().enumerate() {
-            let defender_unit_type = UnitType::ALL[unit_type_index];
-            let dmg = Unit::attack_pts(defender_unit_type, &defender.tech);
-
-            for _ in 0..count {
-                let mut keep_firing = true;
-                while keep_firing && tmp_n_attacker_units > 0 {
-                    // pick a random live attacker
-                    let bound = tmp_n_attacker_units as u64;
-                    let atk_idx = rng.next(bound) as usize;
-
-                    // exactly like the attacker code: mutably borrow once…
-                    let au = &mut attackers[atk_idx];
-                    let max_shield = Unit::shield_pts(au.unit_type, &attacker.tech);
-                    if au.shield > 0.0 {
-                        let pct = dmg / max_shield * 100.0;
-                        if pct <= 1.0 {
-                            break; // bounced
-                        }
-                        let overflow = f64::max(0.0, dmg - au.shield);
-                        au.shield = f64::max(0.0, au.shield - dmg);
-                        au.hull -= overflow;
-                    } else {
-                        au.hull -= dmg;
-                    }
+    /// Removes unit `i` from the live region.
+    fn kill(&mut self, i: usize) {
+        let kind = &self.kinds[self.kind[i] as usize];
+        self.tracker[kind.owner as usize][kind.unit_type as usize] -= 1;
+        let last = self.live - 1;
+        self.hull.swap(i, last);
+        self.shield.swap(i, last);
+        self.kind.swap(i, last);
+        self.live = last;
+    }
 
-                    // if it died, update tracker + swap it out of the live region
-                    if au.hull <= 0.0 {
-                        attacker_units_tracker[au.unit_type as usize] -= 1;
-                        attackers.swap(atk_idx, (tmp_n_attacker_units - 1) as usize);
-                        tmp_n_attacker_units -= 1;
-                        // println!("Attacker unit destroyed");
-                        break;
-                    }
+    /// Drops the units destroyed this round and restores every shield.
+    fn end_round(&mut self) {
+        self.hull.truncate(self.live);
+        self.shield.truncate(self.live);
+        self.kind.truncate(self.live);
+        for (shield, &kind) in self.shield.iter_mut().zip(&self.kind) {
+            *shield = self.kinds[kind as usize].shield;
+        }
+    }
+}
 
-                    // explosion‐kill check
-                    let max_hull = Unit::hull_pts(au.unit_type, &attacker.tech);
-                    if au.hull < 0.7 * max_hull {
-                        let explode_chance = 1.0 - (au.hull / max_hull);
-                        if rng.next_f64() < explode_chance {
-                            attacker_units_tracker[au.unit_type as usize] -= 1;
-                            attackers.swap(atk_idx, (tmp_n_attacker_units - 1) as usize);
-                            tmp_n_attacker_units -= 1;
-                            // println!("Attacker unit destroyed");
-                            break;
-                        }
-                    }
+/// What one shot did to its target.
+pub(crate) enum Hit {
+    /// Too weak for the shield, nothing happened.
+    Bounced,
+    Destroyed,
+    Survived,
+}
+
+/// Applies one shot of `dmg` to a unit with the given hull and shield: the
+/// shield bounce rule, damage and the explosion roll. Shared by all engines.
+pub(crate) fn resolve_hit<R: BattleRng>(
+    hull: &mut f64,
+    shield: &mut f64,
+    kind: &Kind,
+    dmg: f64,
+    rng: &mut R,
+    fire: &mut FireStats,
+) -> Hit {
+    if *shield > 0.0 {
+        // “bounce” rule (a hit too weak for the shield does nothing)
+        if dmg <= kind.bounce_threshold {
+            fire.bounced += 1;
+            fire.absorbed += dmg;
+            return Hit::Bounced;
+        }
+        fire.absorbed += f64::min(dmg, *shield);
+        let overflow = f64::max(0.0, dmg - *shield);
+        *shield = f64::max(0.0, *shield - dmg);
+        *hull -= overflow;
+    } else {
+        *hull -= dmg;
+    }
+    if *hull <= 0.0 {
+        return Hit::Destroyed;
+    }
+    // explosion check once the hull is badly damaged
+    if *hull < kind.explosion_threshold {
+        let explode_chance = 1.0 - (*hull / kind.hull);
+        if rng.next_f64() < explode_chance {
+            return Hit::Destroyed;
+        }
+    }
+    Hit::Survived
+}
+
+/// A side under fire, however an engine stores its units.
+pub(crate) trait Targets {
+    /// Units alive right now.
+    fn live(&self) -> u64;
+
+    /// Resolves one shot on unit `index` of `0..live()` with
+    /// [`resolve_hit`], removing the unit if it is destroyed. Returns the
+    /// unit's type along with the result.
+    fn hit<R: BattleRng>(
+        &mut self,
+        index: u64,
+        dmg: f64,
+        rng: &mut R,
+        fire: &mut FireStats,
+    ) -> (UnitType, Hit);
+}
+
+impl Targets for CombatState<'_> {
+    fn live(&self) -> u64 {
+        self.live as u64
+    }
+
+    fn hit<R: BattleRng>(
+        &mut self,
+        index: u64,
+        dmg: f64,
+        rng: &mut R,
+        fire: &mut FireStats,
+    ) -> (UnitType, Hit) {
+        let i = index as usize;
+        let kind = &self.kinds[self.kind[i] as usize];
+        let hit = resolve_hit(&mut self.hull[i], &mut self.shield[i], kind, dmg, rng, fire);
+        let unit_type = kind.unit_type;
+        if matches!(hit, Hit::Destroyed) {
+            self.kill(i);
+        }
+        (unit_type, hit)
+    }
+}
 
-                    // rapid‐fire loop condition
-                    let rf = rapid_fire_for(defender_unit_type, au.unit_type);
-                    keep_firing = rf > 1 && rng.next_f32() < (rf as f32 - 1.0) / rf as f32;
-                }
+/// One side's volley: every unit alive at the start of the round, counted in
+/// `snapshot`, fires at `targets`. Used for both directions and by all
+/// engines.
+pub(crate) fn fire_volley<T: Targets, R: BattleRng>(
+    ctx: &BattleContext,
+    shooters: &[Kind],
+    snapshot: &[[u64; UnitType::count()]],
+    targets: &mut T,
+    rng: &mut R,
+    fire: &mut FireStats,
+) {
+    for shooter in shooters.iter().filter(|k| k.fires) {
+        let count = snapshot[shooter.owner as usize][shooter.unit_type as usize];
+        let dmg = shooter.attack;
+        for _ in 0..count {
+            let mut keep_firing = true; // rapid fire loop
+            while keep_firing && targets.live() > 0 {
+                fire.shots += 1;
+                fire.damage += dmg;
+                // any live unit of the other side can be hit
+                let index = rng.next(targets.live());
+                keep_firing = match targets.hit(index, dmg, rng, fire) {
+                    (target, Hit::Survived) => {
+                        let rf = ctx.rapid_fire(shooter.unit_type, target);
+                        rf > 0.0 && rng.next_f32() < rf
+                    }
+                    _ => false,
+                };
             }
         }
-        // ────────────────────────────────────────────────────────────────────
-        // reset shields
-        for i in 0..(n_defender_units as usize) {
-            let ut = defenders[i].unit_type;
-            defenders[i].shield = Unit::shield_pts(ut, &defender.tech);
+    }
+}
+
+pub(crate) fn run_battle<R: BattleRng>(
+    battle: &Battle,
+    rng: &mut R,
+    detailed: bool,
+) -> BattleReport {
+    debug_assert!(battle.attackers.len() <= u16::MAX as usize + 1);
+    debug_assert!(battle.defenders.len() <= u16::MAX as usize + 1);
+
+    let ctx = BattleContext::new(battle);
+    let mut attackers = CombatState::deploy(&battle.attackers, &ctx.attackers);
+    let mut defenders = CombatState::deploy(&battle.defenders, &ctx.defenders);
+    let attacker_initial = attackers.tracker.clone();
+    let defender_initial = defenders.tracker.clone();
+    let mut rounds = Vec::new();
+    let mut rounds_fought = 0;
+
+    for round in 0..ctx.rounds {
+        if attackers.live == 0 || defenders.live == 0 {
+            break;
         }
-        for i in 0..(n_attacker_units as usize) {
-            let ut = attackers[i].unit_type;
-            attackers[i].shield = Unit::shield_pts(ut, &attacker.tech);
+        rounds_fought = round + 1;
+        // units destroyed this round still fire, so shoot by the round-start counts
+        let attacker_snapshot = attackers.tracker.clone();
+        let defender_snapshot = defenders.tracker.clone();
+        let mut attacker_fire = FireStats::default();
+        let mut defender_fire = FireStats::default();
+        fire_volley(
+            &ctx,
+            &ctx.attackers,
+            &attacker_snapshot,
+            &mut defenders,
+            rng,
+            &mut attacker_fire,
+        );
+        fire_volley(
+            &ctx,
+            &ctx.defenders,
+            &defender_snapshot,
+            &mut attackers,
+            rng,
+            &mut defender_fire,
+        );
+        defenders.end_round();
+        attackers.end_round();
+
+        if detailed {
+            rounds.push(RoundReport {
+                attackers: attackers.tracker.clone(),
+                defenders: defenders.tracker.clone(),
+                attacker_fire,
+                defender_fire,
+            });
         }
-        n_defender_units = tmp_n_defender_units;
-        n_attacker_units = tmp_n_attacker_units;
     }
 
-    return (attacker_units_tracker, defender_units_tracker);
+    finish_report(
+        battle,
+        participant_reports(attacker_initial, attackers.tracker),
+        participant_reports(defender_initial, defenders.tracker),
+        rounds,
+        rounds_fought,
+        rng,
+    )
+}
+
+/// Everything that happens once the shooting is over: defense rebuild,
+/// debris and moon chance. Shared by all engines.
+pub(crate) fn finish_report<R: BattleRng>(
+    battle: &Battle,
+    attackers: Vec<ParticipantReport>,
+    mut defenders: Vec<ParticipantReport>,
+    rounds: Vec<RoundReport>,
+    rounds_fought: usize,
+    rng: &mut R,
+) -> BattleReport {
+    for defender in &mut defenders {
+        rebuild_defenses(defender, battle.universe.defense_rebuild, rng);
+    }
+    let debris = DebrisField::from_losses(attackers.iter().chain(&defenders), &battle.universe);
+    let moon_chance = debris.moon_chance(&battle.universe.moon);
+    BattleReport {
+        attackers,
+        defenders,
+        rounds,
+        rounds_fought,
+        debris,
+        moon_chance,
+    }
+}
+
+pub(crate) fn participant_reports(
+    initial: Vec<[u64; UnitType::count()]>,
+    remaining: Vec<[u64; UnitType::count()]>,
+) -> Vec<ParticipantReport> {
+    initial
+        .into_iter()
+        .zip(remaining)
+        .map(|(initial, remaining)| ParticipantReport {
+            initial,
+            remaining,
+            rebuilt: [0; UnitType::count()],
+        })
+        .collect()
 }
 
+/// Rolls, one destroyed structure at a time, which defenses come back.
+fn rebuild_defenses<R: BattleRng>(participant: &mut ParticipantReport, chance: f64, rng: &mut R) {
+    let lost = participant.lost();
+    for (&ut, &n) in UnitType::iter().zip(&lost) {
+        if !ut.is_defense() {
+            continue;
+        }
+        participant.rebuilt[ut as usize] =
+            (0..n).filter(|_| rng.next_f64() < chance).count() as u64;
+    }
+}
+
+/// Averages `trials` battles with a master seed drawn from the OS.
 pub fn simulate_battles_avg(
     attacker: &Player,
     defender: &Player,
     trials: usize,
 ) -> ([u64; UnitType::count()], [u64; UnitType::count()]) {
-    // Accumulators in u64, using saturating_add to avoid panics on overflow
-    let mut sum_att = [0u64; UnitType::count()];
-    let mut sum_def = [0u64; UnitType::count()];
+    let seed = FastRng::new(None).next_u64();
+    simulate_battles_avg_seeded(attacker, defender, trials, seed)
+}
 
-    // Run the simulations
-    for _ in 0..trials {
-        let (att_rem, def_rem) = simulate_battle(attacker, defender);
-        for i in 0..UnitType::count() {
-            sum_att[i] = sum_att[i].saturating_add(att_rem[i]);
-            sum_def[i] = sum_def[i].saturating_add(def_rem[i]);
-        }
-    }
+/// Averages `trials` battles; trial `i` is seeded with `derive_seed(seed, i)`,
+/// so the whole run can be replayed, and any single trial reproduced with
+/// [`simulate_battle_seeded`]. Trials run on every available core.
+pub fn simulate_battles_avg_seeded(
+    attacker: &Player,
+    defender: &Player,
+    trials: usize,
+    seed: u64,
+) -> ([u64; UnitType::count()], [u64; UnitType::count()]) {
+    let battle = Battle::new(attacker.clone(), defender.clone());
+    let sums = run_trials(
+        &battle,
+        0..trials,
+        seed,
+        0,
+        Engine::PerUnit,
+        RngBackend::Xoshiro,
+        SurvivorSums::default,
+    );
 
     // Compute rounded averages
     let mut avg_att = [0u64; UnitType::count()];
     let mut avg_def = [0u64; UnitType::count()];
     let inv = 1.0 / (trials as f64);
     for i in 0..UnitType::count() {
-        avg_att[i] = (sum_att[i] as f64 * inv).round() as u64;
-        avg_def[i] = (sum_def[i] as f64 * inv).round() as u64;
+        avg_att[i] = (sums.attacker[i] as f64 * inv).round() as u64;
+        avg_def[i] = (sums.defender[i] as f64 * inv).round() as u64;
     }
 
     (avg_att, avg_def)
 }
+
+/// Survivor totals behind [`simulate_battles_avg_seeded`].
+#[derive(Default)]
+struct SurvivorSums {
+    // Accumulators in u64, using saturating_add to avoid panics on overflow
+    attacker: [u64; UnitType::count()],
+    defender: [u64; UnitType::count()],
+}
+
+impl TrialAccumulator for SurvivorSums {
+    fn add(&mut self, report: &BattleReport) {
+        let (att_rem, def_rem) = (report.attacker_remaining(), report.defender_remaining());
+        for i in 0..UnitType::count() {
+            self.attacker[i] = self.attacker[i].saturating_add(att_rem[i]);
+            self.defender[i] = self.defender[i].saturating_add(def_rem[i]);
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        for i in 0..UnitType::count() {
+            self.attacker[i] = self.attacker[i].saturating_add(other.attacker[i]);
+            self.defender[i] = self.defender[i].saturating_add(other.defender[i]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::grouped::simulate_grouped_battle;
+    use crate::simulator::report::BattleOutcome;
+    use crate::test_util::player;
+    use crate::utils::fast_rng::derive_seed;
+
+    fn skirmish() -> (Player, Player) {
+        (
+            player(&[(UnitType::LightFighter, 200), (UnitType::Cruiser, 10)], 8),
+            player(
+                &[(UnitType::MissileLauncher, 150), (UnitType::LightLaser, 40)],
+                7,
+            ),
+        )
+    }
+
+    #[test]
+    fn same_seed_same_battle() {
+        let (a, d) = skirmish();
+        let first = simulate_battle_seeded(&a, &d, 42);
+        assert_eq!(first, simulate_battle_seeded(&a, &d, 42));
+        // and the seed matters at all
+        assert!((0..8).any(|seed| simulate_battle_seeded(&a, &d, seed) != first));
+    }
+
+    #[test]
+    fn averaged_trials_replay_as_single_battles() {
+        let (a, d) = skirmish();
+        let seed = 0xfeed;
+        assert_eq!(
+            simulate_battles_avg_seeded(&a, &d, 1, seed),
+            simulate_battle_seeded(&a, &d, derive_seed(seed, 0))
+        );
+
+        let trials = 7;
+        let mut sums = ([0u64; UnitType::count()], [0u64; UnitType::count()]);
+        for i in 0..trials {
+            let (att, def) = simulate_battle_seeded(&a, &d, derive_seed(seed, i as u64));
+            for t in 0..UnitType::count() {
+                sums.0[t] += att[t];
+                sums.1[t] += def[t];
+            }
+        }
+        let mean = |sum: u64| (sum as f64 / trials as f64).round() as u64;
+        assert_eq!(
+            simulate_battles_avg_seeded(&a, &d, trials, seed),
+            (sums.0.map(mean), sums.1.map(mean))
+        );
+    }
+
+    #[test]
+    fn volleys_are_the_same_in_both_directions() {
+        let a = player(&[(UnitType::LightFighter, 300), (UnitType::Cruiser, 20)], 3);
+        let b = player(
+            &[
+                (UnitType::MissileLauncher, 200),
+                (UnitType::HeavyFighter, 40),
+                (UnitType::SmallShieldDome, 1),
+            ],
+            5,
+        );
+        let forward = Battle::new(a.clone(), b.clone());
+        let mirrored = Battle::new(b, a);
+        let forward_ctx = BattleContext::new(&forward);
+        let mirrored_ctx = BattleContext::new(&mirrored);
+
+        // `a` shoots at `b`: once as the attacker, once as the defender
+        let shooters = CombatState::deploy(&forward.attackers, &forward_ctx.attackers);
+        let mut forward_targets = CombatState::deploy(&forward.defenders, &forward_ctx.defenders);
+        let mut mirrored_targets =
+            CombatState::deploy(&mirrored.attackers, &mirrored_ctx.attackers);
+        let mut forward_rng = FastRng::seed_from_u64(11);
+        let mut mirrored_rng = FastRng::seed_from_u64(11);
+        for _ in 0..3 {
+            let mut forward_fire = FireStats::default();
+            let mut mirrored_fire = FireStats::default();
+            fire_volley(
+                &forward_ctx,
+                &forward_ctx.attackers,
+                &shooters.tracker,
+                &mut forward_targets,
+                &mut forward_rng,
+                &mut forward_fire,
+            );
+            fire_volley(
+                &mirrored_ctx,
+                &mirrored_ctx.defenders,
+                &shooters.tracker,
+                &mut mirrored_targets,
+                &mut mirrored_rng,
+                &mut mirrored_fire,
+            );
+            forward_targets.end_round();
+            mirrored_targets.end_round();
+
+            assert_eq!(forward_fire, mirrored_fire);
+            assert_eq!(forward_targets.tracker, mirrored_targets.tracker);
+            assert_eq!(forward_targets.hull, mirrored_targets.hull);
+            assert_eq!(forward_targets.shield, mirrored_targets.shield);
+        }
+        assert!(forward_targets.live > 0, "fight should last three volleys");
+    }
+
+    #[test]
+    fn missiles_stay_out_of_the_battle() {
+        let battle = Battle::new(
+            player(&[(UnitType::LightFighter, 10)], 0),
+            player(
+                &[
+                    (UnitType::InterplanetaryMissiles, 10),
+                    (UnitType::AntiBallisticMissiles, 5),
+                ],
+                0,
+            ),
+        );
+        let mut rng = FastRng::seed_from_u64(1);
+        for report in [
+            simulate_acs_battle(&battle, &mut rng),
+            simulate_grouped_battle(&battle, &mut rng),
+        ] {
+            assert_eq!(report.outcome(), BattleOutcome::AttackerWin);
+            assert_eq!(report.rounds_fought, 0);
+            assert_eq!(report.defenders[0].initial, [0; UnitType::count()]);
+            assert_eq!(
+                report.attacker_remaining()[UnitType::LightFighter as usize],
+                10
+            );
+        }
+    }
+
+    #[test]
+    fn only_lost_defenses_are_rebuilt() {
+        let mut initial = [0; UnitType::count()];
+        initial[UnitType::LightFighter as usize] = 10;
+        initial[UnitType::MissileLauncher as usize] = 10;
+        initial[UnitType::LightLaser as usize] = 4;
+        let mut remaining = [0; UnitType::count()];
+        remaining[UnitType::LightLaser as usize] = 1;
+        let report = || ParticipantReport {
+            initial,
+            remaining,
+            rebuilt: [0; UnitType::count()],
+        };
+        let mut rng = FastRng::seed_from_u64(3);
+
+        let mut none = report();
+        rebuild_defenses(&mut none, 0.0, &mut rng);
+        assert_eq!(none.rebuilt, [0; UnitType::count()]);
+        assert_eq!(none.permanently_lost(), none.lost());
+
+        let mut all = report();
+        rebuild_defenses(&mut all, 1.0, &mut rng);
+        assert_eq!(all.rebuilt[UnitType::LightFighter as usize], 0);
+        assert_eq!(all.rebuilt[UnitType::MissileLauncher as usize], 10);
+        assert_eq!(all.rebuilt[UnitType::LightLaser as usize], 3);
+        let permanently_lost = all.permanently_lost();
+        for ((&lost, &rebuilt), &gone) in all.lost().iter().zip(&all.rebuilt).zip(&permanently_lost)
+        {
+            assert_eq!(gone, lost - rebuilt);
+        }
+        assert_eq!(permanently_lost[UnitType::LightFighter as usize], 10);
+        assert_eq!(permanently_lost[UnitType::MissileLauncher as usize], 0);
+
+        let mut some = report();
+        rebuild_defenses(&mut some, 0.5, &mut rng);
+        assert!(some.rebuilt[UnitType::MissileLauncher as usize] <= 10);
+        assert_eq!(some.rebuilt[UnitType::LightFighter as usize], 0);
+    }
+
+    #[test]
+    fn each_participant_fights_with_its_own_research() {
+        let mut battle = Battle::new(
+            player(&[(UnitType::LightFighter, 100)], 0),
+            player(&[(UnitType::MissileLauncher, 300)], 0),
+        );
+        battle
+            .attackers
+            .push(player(&[(UnitType::Cruiser, 20)], 10));
+        let ctx = BattleContext::new(&battle);
+        assert_eq!(ctx.attackers.len(), 2);
+        assert_eq!((ctx.attackers[0].owner, ctx.attackers[0].attack), (0, 50.0));
+        assert_eq!(
+            (ctx.attackers[1].owner, ctx.attackers[1].attack),
+            (1, 800.0)
+        );
+        assert_eq!(ctx.attackers[1].hull, 5_400.0);
+
+        let mut rng = FastRng::seed_from_u64(8);
+        for _ in 0..20 {
+            let report = simulate_acs_battle(&battle, &mut rng);
+            assert_eq!(report.attackers.len(), 2);
+            let fleets = [(UnitType::LightFighter, 100), (UnitType::Cruiser, 20)];
+            for (participant, (ut, n)) in report.attackers.iter().zip(fleets) {
+                assert_eq!(participant.initial[ut as usize], n);
+                assert_eq!(participant.initial.iter().sum::<u64>(), n);
+                // every loss lands on the participant that owned the unit
+                let lost = participant.lost();
+                assert_eq!(lost.iter().sum::<u64>(), lost[ut as usize]);
+            }
+        }
+        let lost = |i: usize| report_losses(&battle, i);
+        assert!(lost(0) > 0 && lost(1) > 0);
+    }
+
+    /// Units attacking participant `i` lost over a few seeded battles.
+    fn report_losses(battle: &Battle, i: usize) -> u64 {
+        (0..5)
+            .map(|seed| {
+                let report = simulate_acs_battle(battle, &mut FastRng::seed_from_u64(seed));
+                report.attackers[i].lost().iter().sum::<u64>()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn a_wiped_out_side_ends_the_battle() {
+        let battle = Battle::new(
+            player(&[(UnitType::Battleship, 100)], 0),
+            player(&[(UnitType::LightFighter, 10)], 0),
+        );
+        let report = simulate_acs_battle_detailed(&battle, &mut FastRng::seed_from_u64(1));
+        assert_eq!(report.outcome(), BattleOutcome::AttackerWin);
+        assert_eq!(report.rounds_fought, 1);
+        assert_eq!(report.rounds.len(), 1);
+
+        // neither cargo can break the other's shield in six rounds
+        let battle = Battle::new(
+            player(&[(UnitType::LargeCargo, 1)], 0),
+            player(&[(UnitType::LargeCargo, 1)], 0),
+        );
+        let report = simulate_acs_battle_detailed(&battle, &mut FastRng::seed_from_u64(1));
+        assert_eq!(report.outcome(), BattleOutcome::Draw);
+        assert_eq!(report.rounds_fought, 6);
+        assert_eq!(report.rounds.len(), 6);
+    }
+
+    #[test]
+    fn fire_stats_add_up_each_round() {
+        let mut battle = Battle::new(
+            player(
+                &[
+                    (UnitType::LightFighter, 200),
+                    (UnitType::SolarSatellite, 20),
+                ],
+                0,
+            ),
+            player(
+                &[(UnitType::Battleship, 10), (UnitType::MissileLauncher, 100)],
+                0,
+            ),
+        );
+        battle.universe.combat = crate::types::universe::CombatRules::no_rapid_fire();
+        let report = simulate_acs_battle_detailed(&battle, &mut FastRng::seed_from_u64(4));
+        let mut before = report.attackers[0].initial;
+        let mut bounced = 0;
+        for round in &report.rounds {
+            let fighters = before[UnitType::LightFighter as usize];
+            let satellites = before[UnitType::SolarSatellite as usize];
+            let fire = &round.attacker_fire;
+            // without rapid fire every unit alive at the start shoots once,
+            // unless the targets run out first
+            if round.defender_units().iter().any(|&n| n > 0) {
+                assert_eq!(fire.shots, fighters + satellites);
+            } else {
+                assert!(fire.shots <= fighters + satellites);
+            }
+            assert!(fire.damage >= fire.shots as f64);
+            assert!(fire.damage <= 50.0 * fire.shots as f64);
+            // only satellite shots are weak enough to bounce, off battleships
+            assert!(fire.bounced <= satellites);
+            assert!(fire.absorbed <= fire.damage);
+            bounced += fire.bounced;
+            before = round.attackers[0];
+        }
+        assert!(bounced > 0);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::types::battle::Battle;
+use crate::types::player::Player;
+use crate::types::rapid_fire_for;
+use crate::types::unit::Unit;
+use crate::types::unit_type::{UnitType, units_map_to_array};
+use crate::types::universe::CombatRules;
+
+/// Combat values of one kind of unit, i.e. one type owned by one
+/// participant, with that participant's research applied.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Kind {
+    pub(crate) owner: u16,
+    pub(crate) unit_type: UnitType,
+    pub(crate) attack: f64,
+    pub(crate) hull: f64,
+    pub(crate) shield: f64,
+    /// Shots of at most this much damage bounce off a raised shield.
+    pub(crate) bounce_threshold: f64,
+    /// Below this hull a hit unit may explode.
+    pub(crate) explosion_threshold: f64,
+    /// Whether the kind fires at all under the battle's rules.
+    pub(crate) fires: bool,
+}
+
+/// Everything the firing loop looks up, computed once per battle.
+///
+/// Kinds are listed participant after participant and in `UnitType::ALL`
+/// order within each participant, leaving out types the participant does
+/// not field and missiles.
+pub(crate) struct BattleContext {
+    pub(crate) rounds: usize,
+    pub(crate) attackers: Vec<Kind>,
+    pub(crate) defenders: Vec<Kind>,
+    /// `rapid_fire[shooter][target]` = chance to fire again after a shot,
+    /// 0 when the shooter has no rapid fire against the target or rapid fire
+    /// is switched off.
+    rapid_fire: [[f32; UnitType::count()]; UnitType::count()],
+}
+
+impl BattleContext {
+    pub(crate) fn new(battle: &Battle) -> Self {
+        let rules = &battle.universe.combat;
+        let rapid_fire = std::array::from_fn(|shooter| {
+            std::array::from_fn(|target| {
+                let rf = rapid_fire_for(UnitType::ALL[shooter], UnitType::ALL[target]);
+                if rules.rapid_fire && rf > 1 {
+                    (rf as f32 - 1.0) / rf as f32
+                } else {
+                    0.0
+                }
+            })
+        });
+        Self {
+            rounds: rules.rounds,
+            attackers: kinds(&battle.attackers, rules),
+            defenders: kinds(&battle.defenders, rules),
+            rapid_fire,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn rapid_fire(&self, shooter: UnitType, target: UnitType) -> f32 {
+        self.rapid_fire[shooter as usize][target as usize]
+    }
+}
+
+/// Units of `player` that take part in a battle, indexed by `UnitType`.
+/// Missiles stay in their silos and are left out.
+pub(crate) fn combat_units(player: &Player) -> [u64; UnitType::count()] {
+    let mut counts = units_map_to_array(&player.fleet.units);
+    for (&ut, count) in UnitType::iter().zip(&mut counts) {
+        if ut.is_missile() {
+            *count = 0;
+        }
+    }
+    counts
+}
+
+fn kinds(players: &[Player], rules: &CombatRules) -> Vec<Kind> {
+    let mut kinds = Vec::new();
+    for (owner, player) in players.iter().enumerate() {
+        let counts = combat_units(player);
+        for (&ut, &count) in UnitType::iter().zip(&counts) {
+            if count == 0 {
+                continue;
+            }
+            let hull = Unit::hull_pts(ut, &player.tech);
+            let shield = Unit::shield_pts(ut, &player.tech);
+            kinds.push(Kind {
+                owner: owner as u16,
+                unit_type: ut,
+                attack: Unit::attack_pts(ut, &player.tech),
+                hull,
+                shield,
+                bounce_threshold: shield * rules.bounce_threshold,
+                explosion_threshold: hull * rules.explosion_threshold,
+                fires: rules.defenses_shoot || !ut.is_defense(),
+            });
+        }
+    }
+    kinds
+}
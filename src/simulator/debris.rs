@@ -0,0 +1,155 @@
+use crate::simulator::report::ParticipantReport;
+use crate::types::unit_stats::UNIT_STATS;
+use crate::types::unit_type::UnitType;
+use crate::types::universe::{MoonRules, UniverseSettings};
+use serde::Serialize;
+
+/// Metal and crystal floating at the battle coordinates.
+///
+/// A single battle always yields whole resources; Monte-Carlo summaries use
+/// the same type for the (fractional) expected field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DebrisField {
+    pub metal: f64,
+    pub crystal: f64,
+}
+
+impl DebrisField {
+    /// Debris left by the losses of every participant on both sides.
+    pub fn from_losses<'a>(
+        participants: impl IntoIterator<Item = &'a ParticipantReport>,
+        universe: &UniverseSettings,
+    ) -> Self {
+        let (mut metal, mut crystal) = (0.0, 0.0);
+        for participant in participants {
+            for (&ut, &lost) in UnitType::iter().zip(&participant.lost()) {
+                if lost == 0 {
+                    continue;
+                }
+                let share = if ut.is_defense() {
+                    universe.defense_debris
+                } else {
+                    universe.fleet_debris
+                };
+                let stats = UNIT_STATS[ut];
+                metal += lost as f64 * stats.metal_cost * share;
+                crystal += lost as f64 * stats.crystal_cost * share;
+            }
+        }
+        Self {
+            metal: metal.floor(),
+            crystal: crystal.floor(),
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.metal + self.crystal
+    }
+
+    /// Moon chance in whole percent: one percent per
+    /// `rules.debris_per_percent` of debris, capped at `rules.max_chance`.
+    /// With `debris_per_percent` at 0 any debris at all gives the cap.
+    pub fn moon_chance(&self, rules: &MoonRules) -> u32 {
+        let chance = (self.total() / rules.debris_per_percent).floor();
+        u32::min(chance as u32, rules.max_chance)
+    }
+
+    /// Recyclers needed to harvest the whole field in one trip.
+    pub fn recyclers_needed(&self) -> u64 {
+        self.carriers_needed(UnitType::Recycler)
+    }
+
+    /// Pathfinders needed to harvest the whole field in one trip.
+    pub fn pathfinders_needed(&self) -> u64 {
+        self.carriers_needed(UnitType::PathFinder)
+    }
+
+    fn carriers_needed(&self, carrier: UnitType) -> u64 {
+        (self.total() / UNIT_STATS[carrier].cargo).ceil() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lost(units: &[(UnitType, u64)]) -> ParticipantReport {
+        let mut initial = [0; UnitType::count()];
+        for &(ut, n) in units {
+            initial[ut as usize] = n;
+        }
+        ParticipantReport {
+            initial,
+            remaining: [0; UnitType::count()],
+            rebuilt: [0; UnitType::count()],
+        }
+    }
+
+    #[test]
+    fn ships_and_defenses_use_their_own_share() {
+        let attacker = lost(&[(UnitType::LightFighter, 10)]);
+        let defender = lost(&[(UnitType::MissileLauncher, 100)]);
+        let universe = UniverseSettings::default();
+        assert_eq!(
+            DebrisField::from_losses([&attacker, &defender], &universe),
+            DebrisField {
+                metal: 9_000.0,
+                crystal: 3_000.0
+            }
+        );
+        let universe = UniverseSettings {
+            fleet_debris: 0.0,
+            defense_debris: 0.1,
+            ..UniverseSettings::default()
+        };
+        assert_eq!(
+            DebrisField::from_losses([&attacker, &defender], &universe),
+            DebrisField {
+                metal: 20_000.0,
+                crystal: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn debris_is_rounded_down() {
+        let universe = UniverseSettings {
+            fleet_debris: 0.3333,
+            ..UniverseSettings::default()
+        };
+        let field = DebrisField::from_losses([&lost(&[(UnitType::LightFighter, 1)])], &universe);
+        assert_eq!((field.metal, field.crystal), (999.0, 333.0));
+    }
+
+    #[test]
+    fn harvesting_rounds_trips_up() {
+        let field = DebrisField {
+            metal: 30_000.0,
+            crystal: 10_001.0,
+        };
+        assert_eq!(field.recyclers_needed(), 3);
+        assert_eq!(field.pathfinders_needed(), 5);
+        assert_eq!(DebrisField::default().recyclers_needed(), 0);
+    }
+
+    #[test]
+    fn moon_chance_grows_with_debris_up_to_the_cap() {
+        let rules = MoonRules::default();
+        let field = |metal| DebrisField {
+            metal,
+            crystal: 0.0,
+        };
+        assert_eq!(field(0.0).moon_chance(&rules), 0);
+        assert_eq!(field(99_999.0).moon_chance(&rules), 0);
+        assert_eq!(field(100_000.0).moon_chance(&rules), 1);
+        assert_eq!(field(1_550_000.0).moon_chance(&rules), 15);
+        assert_eq!(field(1e12).moon_chance(&rules), 20);
+
+        let free = MoonRules {
+            debris_per_percent: 0.0,
+            ..rules
+        };
+        assert_eq!(field(0.0).moon_chance(&free), 0);
+        assert_eq!(field(1.0).moon_chance(&free), 20);
+    }
+}
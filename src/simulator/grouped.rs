@@ -0,0 +1,298 @@
+//! Battle engine that keeps undamaged units as counted groups.
+//!
+//! The per-unit engine in [`battle`](crate::simulator::battle) keeps a hull,
+//! a shield and a kind entry for every ship, which does not scale to fleets
+//! of tens of millions. Here
+//! every `(participant, type)` pair is a single group of pristine units; a
+//! unit is only split out into its own record once a shot actually damages
+//! it, and merged back when its shield is restored and its hull is intact.
+//! Memory therefore grows with the number of damaged units, not fleet size.
+//!
+//! Shots go through the per-unit engine's `fire_volley` and `resolve_hit`;
+//! only target lookup differs, so both produce the same outcome distribution.
+//! They do not produce the same battle for a given seed, because targets are
+//! numbered differently.
+
+use crate::simulator::battle::{
+    Hit, Targets, finish_report, fire_volley, participant_reports, resolve_hit,
+};
+use crate::simulator::context::{BattleContext, Kind, combat_units};
+use crate::simulator::report::{BattleReport, FireStats, RoundReport};
+use crate::types::battle::Battle;
+use crate::types::player::Player;
+use crate::types::unit_type::UnitType;
+use crate::utils::rng::BattleRng;
+
+/// Simulates a battle with the grouped engine.
+pub fn simulate_grouped_battle<R: BattleRng>(battle: &Battle, rng: &mut R) -> BattleReport {
+    run_grouped_battle(battle, rng, false)
+}
+
+/// Like [`simulate_grouped_battle`], recording every round for the combat
+/// report.
+pub fn simulate_grouped_battle_detailed<R: BattleRng>(
+    battle: &Battle,
+    rng: &mut R,
+) -> BattleReport {
+    run_grouped_battle(battle, rng, true)
+}
+
+/// Pristine units of one kind; groups line up with the side's kinds in the
+/// [`BattleContext`].
+struct Group {
+    pristine: u64,
+}
+
+/// A unit that has taken damage this round or earlier.
+struct Damaged {
+    group: u32,
+    hull: f64,
+    shield: f64,
+}
+
+enum Target {
+    Pristine(usize),
+    Damaged(usize),
+}
+
+struct GroupedSide<'a> {
+    kinds: &'a [Kind],
+    groups: Vec<Group>,
+    damaged: Vec<Damaged>,
+    tracker: Vec<[u64; UnitType::count()]>,
+    live: u64,
+}
+
+impl<'a> GroupedSide<'a> {
+    fn deploy(players: &[Player], kinds: &'a [Kind]) -> Self {
+        let tracker: Vec<_> = players.iter().map(combat_units).collect();
+        let groups = kinds
+            .iter()
+            .map(|kind| Group {
+                pristine: tracker[kind.owner as usize][kind.unit_type as usize],
+            })
+            .collect();
+        let live = tracker.iter().flatten().sum();
+        Self {
+            kinds,
+            groups,
+            damaged: Vec::new(),
+            tracker,
+            live,
+        }
+    }
+
+    /// Maps a uniform index in `0..live` to a unit: pristine groups first,
+    /// then the damaged units.
+    fn pick(&self, mut index: u64) -> Target {
+        for (g, group) in self.groups.iter().enumerate() {
+            if index < group.pristine {
+                return Target::Pristine(g);
+            }
+            index -= group.pristine;
+        }
+        Target::Damaged(index as usize)
+    }
+
+    fn kill(&mut self, group: usize) {
+        let kind = &self.kinds[group];
+        self.tracker[kind.owner as usize][kind.unit_type as usize] -= 1;
+        self.live -= 1;
+    }
+
+    /// Restores every shield and folds units whose hull is still intact
+    /// back into their group.
+    fn reset_shields(&mut self) {
+        let (kinds, groups) = (self.kinds, &mut self.groups);
+        self.damaged.retain_mut(|d| {
+            let kind = &kinds[d.group as usize];
+            if d.hull >= kind.hull {
+                groups[d.group as usize].pristine += 1;
+                false
+            } else {
+                d.shield = kind.shield;
+                true
+            }
+        });
+    }
+}
+
+impl Targets for GroupedSide<'_> {
+    fn live(&self) -> u64 {
+        self.live
+    }
+
+    fn hit<R: BattleRng>(
+        &mut self,
+        index: u64,
+        dmg: f64,
+        rng: &mut R,
+        fire: &mut FireStats,
+    ) -> (UnitType, Hit) {
+        let (group, hit) = match self.pick(index) {
+            Target::Pristine(g) => {
+                let kind = &self.kinds[g];
+                let (mut hull, mut shield) = (kind.hull, kind.shield);
+                let hit = resolve_hit(&mut hull, &mut shield, kind, dmg, rng, fire);
+                if !matches!(hit, Hit::Bounced) {
+                    self.groups[g].pristine -= 1;
+                }
+                if matches!(hit, Hit::Survived) {
+                    self.damaged.push(Damaged {
+                        group: g as u32,
+                        hull,
+                        shield,
+                    });
+                }
+                (g, hit)
+            }
+            Target::Damaged(i) => {
+                let d = &mut self.damaged[i];
+                let g = d.group as usize;
+                let kind = &self.kinds[g];
+                let hit = resolve_hit(&mut d.hull, &mut d.shield, kind, dmg, rng, fire);
+                if matches!(hit, Hit::Destroyed) {
+                    self.damaged.swap_remove(i);
+                }
+                (g, hit)
+            }
+        };
+        if matches!(hit, Hit::Destroyed) {
+            self.kill(group);
+        }
+        (self.kinds[group].unit_type, hit)
+    }
+}
+
+fn run_grouped_battle<R: BattleRng>(battle: &Battle, rng: &mut R, detailed: bool) -> BattleReport {
+    let ctx = BattleContext::new(battle);
+    let mut attackers = GroupedSide::deploy(&battle.attackers, &ctx.attackers);
+    let mut defenders = GroupedSide::deploy(&battle.defenders, &ctx.defenders);
+    let attacker_initial = attackers.tracker.clone();
+    let defender_initial = defenders.tracker.clone();
+    let mut rounds = Vec::new();
+    let mut rounds_fought = 0;
+
+    for round in 0..ctx.rounds {
+        if attackers.live == 0 || defenders.live == 0 {
+            break;
+        }
+        rounds_fought = round + 1;
+        let attacker_snapshot = attackers.tracker.clone();
+        let defender_snapshot = defenders.tracker.clone();
+        let mut attacker_fire = FireStats::default();
+        let mut defender_fire = FireStats::default();
+        fire_volley(
+            &ctx,
+            &ctx.attackers,
+            &attacker_snapshot,
+            &mut defenders,
+            rng,
+            &mut attacker_fire,
+        );
+        fire_volley(
+            &ctx,
+            &ctx.defenders,
+            &defender_snapshot,
+            &mut attackers,
+            rng,
+            &mut defender_fire,
+        );
+        attackers.reset_shields();
+        defenders.reset_shields();
+
+        if detailed {
+            rounds.push(RoundReport {
+                attackers: attackers.tracker.clone(),
+                defenders: defenders.tracker.clone(),
+                attacker_fire,
+                defender_fire,
+            });
+        }
+    }
+
+    finish_report(
+        battle,
+        participant_reports(attacker_initial, attackers.tracker),
+        participant_reports(defender_initial, defenders.tracker),
+        rounds,
+        rounds_fought,
+        rng,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulator::monte_carlo::{Engine, MonteCarloConfig, simulate_battles_stats};
+    use crate::test_util::{battle, player};
+    use crate::types::battle::Battle;
+    use crate::types::unit_type::UnitType;
+
+    /// Both engines agree on every survivor mean within a few standard
+    /// errors, and on the spread within a few percent.
+    fn assert_same_distribution(battle: &Battle) {
+        let run = |engine| {
+            simulate_battles_stats(
+                battle,
+                &MonteCarloConfig {
+                    trials: 4000,
+                    seed: Some(3),
+                    engine,
+                    ..MonteCarloConfig::default()
+                },
+            )
+        };
+        let per_unit = run(Engine::PerUnit);
+        let grouped = run(Engine::Grouped);
+        let trials = per_unit.trials as f64;
+        let sides = [
+            (&per_unit.attacker, &grouped.attacker),
+            (&per_unit.defender, &grouped.defender),
+        ];
+        for (a, b) in sides {
+            for (&ut, (a, b)) in UnitType::iter().zip(a.iter().zip(b)) {
+                let se = ((a.std_dev.powi(2) + b.std_dev.powi(2)) / trials).sqrt();
+                assert!(
+                    (a.mean - b.mean).abs() <= 4.0 * se + 1e-9,
+                    "{ut}: mean {} vs {}",
+                    a.mean,
+                    b.mean
+                );
+                assert!(
+                    (a.std_dev - b.std_dev).abs() <= 0.1 * a.std_dev.max(b.std_dev) + 0.05,
+                    "{ut}: std dev {} vs {}",
+                    a.std_dev,
+                    b.std_dev
+                );
+            }
+        }
+        assert!((per_unit.attacker_win - grouped.attacker_win).abs() < 0.04);
+    }
+
+    #[test]
+    fn engines_agree_on_a_close_fight() {
+        assert_same_distribution(&battle(
+            &[(UnitType::LightFighter, 150), (UnitType::Cruiser, 15)],
+            &[
+                (UnitType::MissileLauncher, 135),
+                (UnitType::LightLaser, 40),
+                (UnitType::SmallShieldDome, 1),
+            ],
+        ));
+    }
+
+    #[test]
+    fn engines_agree_on_a_three_way_outcome() {
+        assert_same_distribution(&Battle::new(
+            player(&[(UnitType::Battleship, 20), (UnitType::Bomber, 10)], 10),
+            player(
+                &[
+                    (UnitType::HeavyFighter, 100),
+                    (UnitType::GaussCannon, 10),
+                    (UnitType::PlasmaTurret, 2),
+                ],
+                9,
+            ),
+        ));
+    }
+}
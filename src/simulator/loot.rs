@@ -0,0 +1,120 @@
+use crate::types::resources::Resources;
+use crate::types::unit_stats::UNIT_STATS;
+use crate::types::unit_type::UnitType;
+
+/// What the surviving attackers carry home.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Loot {
+    pub resources: Resources,
+    /// Cargo space of the surviving fleet.
+    pub capacity: f64,
+    /// `true` when more was up for grabs than the fleet could carry.
+    pub capacity_limited: bool,
+}
+
+/// Total cargo space of a fleet.
+pub fn cargo_capacity(units: &[u64; UnitType::count()]) -> f64 {
+    UnitType::iter()
+        .zip(units)
+        .map(|(&ut, &n)| n as f64 * UNIT_STATS[ut].cargo)
+        .sum()
+}
+
+/// Plunders `planet` with the fleet `survivors`.
+///
+/// `plunder` is the share of each resource that can be taken (0.5 on most
+/// servers, more for some player classes and inactive targets). Only call
+/// this for battles the attacker won.
+///
+/// The available resources are loaded the way the game does it:
+/// 1. metal up to a third of the capacity,
+/// 2. crystal up to half of the remaining capacity,
+/// 3. deuterium into whatever capacity is left,
+/// 4. metal again up to half of the remaining capacity,
+/// 5. crystal again into the rest.
+pub fn plunder(planet: &Resources, plunder: f64, survivors: &[u64; UnitType::count()]) -> Loot {
+    let capacity = cargo_capacity(survivors);
+    let available = Resources::new(
+        (planet.metal * plunder).floor(),
+        (planet.crystal * plunder).floor(),
+        (planet.deuterium * plunder).floor(),
+    );
+
+    let mut left = available;
+    let mut free = capacity;
+    let mut metal = load(&mut left.metal, &mut free, capacity / 3.0);
+    let half = free / 2.0;
+    let mut crystal = load(&mut left.crystal, &mut free, half);
+    let all = free;
+    let deuterium = load(&mut left.deuterium, &mut free, all);
+    let half = free / 2.0;
+    metal += load(&mut left.metal, &mut free, half);
+    let all = free;
+    crystal += load(&mut left.crystal, &mut free, all);
+
+    Loot {
+        resources: Resources::new(metal, crystal, deuterium),
+        capacity,
+        capacity_limited: left.total() > 0.0,
+    }
+}
+
+/// Moves up to `limit` whole units from `stock` into the `free` cargo space.
+fn load(stock: &mut f64, free: &mut f64, limit: f64) -> f64 {
+    let amount = stock.min(limit).min(*free).max(0.0).floor();
+    *stock -= amount;
+    *free -= amount;
+    amount
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn large_cargos(n: u64) -> [u64; UnitType::count()] {
+        let mut units = [0; UnitType::count()];
+        units[UnitType::LargeCargo as usize] = n;
+        units
+    }
+
+    #[test]
+    fn everything_fits() {
+        let planet = Resources::new(60_000.0, 40_000.0, 20_000.0);
+        let loot = plunder(&planet, 0.5, &large_cargos(10));
+        assert_eq!(loot.resources, Resources::new(30_000.0, 20_000.0, 10_000.0));
+        assert_eq!(loot.capacity, 250_000.0);
+        assert!(!loot.capacity_limited);
+    }
+
+    #[test]
+    fn full_hold_is_split_in_thirds() {
+        // 50k of each on offer, 25k of space: a third of metal, half the rest
+        // in crystal, deuterium fills up
+        let planet = Resources::new(100_000.0, 100_000.0, 100_000.0);
+        let loot = plunder(&planet, 0.5, &large_cargos(1));
+        assert_eq!(loot.resources, Resources::new(8_333.0, 8_333.0, 8_334.0));
+        assert!(loot.capacity_limited);
+    }
+
+    #[test]
+    fn second_pass_tops_up_metal() {
+        // 100k metal and 10k crystal on offer, 50k of space:
+        // 16,666 metal, 10,000 crystal, no deuterium, then half of the
+        // remaining 23,334 as metal; the rest stays empty
+        let planet = Resources::new(200_000.0, 20_000.0, 0.0);
+        let loot = plunder(&planet, 0.5, &large_cargos(2));
+        assert_eq!(loot.resources, Resources::new(28_333.0, 10_000.0, 0.0));
+        assert!(loot.capacity_limited);
+    }
+
+    #[test]
+    fn second_pass_tops_up_crystal() {
+        // 10k metal and 100k crystal on offer, 50k of space:
+        // 10,000 metal, 20,000 crystal, no deuterium, metal is gone, and
+        // crystal takes the remaining 20,000
+        let planet = Resources::new(20_000.0, 200_000.0, 0.0);
+        let loot = plunder(&planet, 0.5, &large_cargos(2));
+        assert_eq!(loot.resources, Resources::new(10_000.0, 40_000.0, 0.0));
+        assert!(loot.capacity_limited);
+    }
+}
@@ -1,5 +1,25 @@
+pub mod adaptive;
 pub mod battle;
-#[allow(unused)]
-pub use battle::simulate_battle;
-#[allow(unused)]
-pub use battle::simulate_battles_avg;
+pub(crate) mod context;
+pub mod debris;
+pub mod grouped;
+pub mod loot;
+pub mod monte_carlo;
+pub mod profit;
+pub mod report;
+pub mod stats;
+pub use adaptive::{
+    AdaptiveStatistics, ConvergenceCriteria, ConvergenceMetric, simulate_battles_adaptive,
+};
+pub use battle::{
+    simulate_acs_battle, simulate_acs_battle_detailed, simulate_battle, simulate_battle_detailed,
+    simulate_battle_seeded, simulate_battle_with_rng, simulate_battles_avg,
+    simulate_battles_avg_seeded,
+};
+pub use debris::DebrisField;
+pub use grouped::{simulate_grouped_battle, simulate_grouped_battle_detailed};
+pub use loot::{Loot, cargo_capacity, plunder};
+pub use monte_carlo::{Engine, MonteCarloConfig, RngBackend, simulate_battles_stats};
+pub use profit::{ProfitReport, RaidPlan, TradeRatios, analyze_raid, resource_value};
+pub use report::{BattleOutcome, BattleReport, FireStats, ParticipantReport, RoundReport};
+pub use stats::{BattleStatistics, UnitSummary};
@@ -0,0 +1,231 @@
+use crate::simulator::battle::run_battle;
+use crate::simulator::grouped::simulate_grouped_battle;
+use crate::simulator::report::BattleReport;
+use crate::simulator::stats::{BattleStatistics, StatsAccumulator};
+use crate::types::battle::Battle;
+use crate::utils::counter_rng::CounterRng;
+use crate::utils::fast_rng::FastRng;
+use crate::utils::rng::BattleRng;
+use crate::utils::wyrand::WyRand;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Trials handed to a worker at a time. Fixed, so that the way trials are
+/// grouped (and merged) never depends on the number of threads.
+pub(crate) const BLOCK_TRIALS: usize = 64;
+
+/// Which battle engine runs the trials.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Engine {
+    /// One record per unit; fastest for ordinary fleet sizes.
+    #[default]
+    PerUnit,
+    /// Undamaged units kept as counted groups; for fleets too large to
+    /// materialize unit by unit.
+    Grouped,
+}
+
+impl Engine {
+    pub(crate) fn run<R: BattleRng>(self, battle: &Battle, rng: &mut R) -> BattleReport {
+        match self {
+            Engine::PerUnit => run_battle(battle, rng, false),
+            Engine::Grouped => simulate_grouped_battle(battle, rng),
+        }
+    }
+}
+
+/// Random number generator each trial draws from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RngBackend {
+    /// [`FastRng`], xoshiro256++.
+    #[default]
+    Xoshiro,
+    /// [`WyRand`].
+    WyRand,
+    /// [`CounterRng`]; each trial opens its own stream of the master seed.
+    Counter,
+}
+
+/// Settings of a Monte-Carlo run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonteCarloConfig {
+    pub trials: usize,
+    /// Master seed; `None` draws one from the OS.
+    pub seed: Option<u64>,
+    /// Survivor percentiles to report, in percent.
+    pub percentiles: Vec<f64>,
+    /// Worker threads; 0 uses every available core.
+    pub threads: usize,
+    /// Engine that runs each trial.
+    pub engine: Engine,
+    pub rng: RngBackend,
+}
+
+impl Default for MonteCarloConfig {
+    fn default() -> Self {
+        Self {
+            trials: 1000,
+            seed: None,
+            percentiles: vec![5.0, 25.0, 50.0, 75.0, 95.0],
+            threads: 0,
+            engine: Engine::PerUnit,
+            rng: RngBackend::Xoshiro,
+        }
+    }
+}
+
+impl MonteCarloConfig {
+    pub(crate) fn master_seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| FastRng::new(None).next_u64())
+    }
+}
+
+/// Per-trial summary that can be built up in pieces and combined.
+pub(crate) trait TrialAccumulator: Send {
+    fn add(&mut self, report: &BattleReport);
+    fn merge(&mut self, other: Self);
+}
+
+/// Number of threads meant by a `threads` setting.
+pub(crate) fn worker_count(threads: usize) -> usize {
+    if threads > 0 {
+        threads
+    } else {
+        thread::available_parallelism().map_or(1, |n| n.get())
+    }
+}
+
+/// Adds the outcome of each trial in `trials` to `acc`, trial `i` drawing
+/// from stream `i` of `seed`.
+fn run_range<A: TrialAccumulator, R: BattleRng>(
+    acc: &mut A,
+    battle: &Battle,
+    trials: Range<usize>,
+    seed: u64,
+    engine: Engine,
+) {
+    for trial in trials {
+        let mut rng = R::from_stream(seed, trial as u64);
+        acc.add(&engine.run(battle, &mut rng));
+    }
+}
+
+/// Runs trials `trials` of `battle` on `threads` workers.
+///
+/// Trial `i` always uses stream `i` of `seed`, and block results are merged
+/// in block order, so the outcome is the same for every thread count.
+pub(crate) fn run_trials<A, F>(
+    battle: &Battle,
+    trials: Range<usize>,
+    seed: u64,
+    threads: usize,
+    engine: Engine,
+    backend: RngBackend,
+    make: F,
+) -> A
+where
+    A: TrialAccumulator,
+    F: Fn() -> A + Sync,
+{
+    let blocks = trials.len().div_ceil(BLOCK_TRIALS);
+    let run_block = |block: usize| {
+        let mut acc = make();
+        let first = trials.start + block * BLOCK_TRIALS;
+        let range = first..usize::min(first + BLOCK_TRIALS, trials.end);
+        match backend {
+            RngBackend::Xoshiro => run_range::<_, FastRng>(&mut acc, battle, range, seed, engine),
+            RngBackend::WyRand => run_range::<_, WyRand>(&mut acc, battle, range, seed, engine),
+            RngBackend::Counter => {
+                run_range::<_, CounterRng>(&mut acc, battle, range, seed, engine)
+            }
+        }
+        acc
+    };
+
+    let threads = worker_count(threads).min(blocks);
+    let mut results: Vec<Option<A>> = (0..blocks).map(|_| None).collect();
+    if threads <= 1 {
+        for (block, slot) in results.iter_mut().enumerate() {
+            *slot = Some(run_block(block));
+        }
+    } else {
+        let next_block = AtomicUsize::new(0);
+        thread::scope(|s| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let block = next_block.fetch_add(1, Ordering::Relaxed);
+                            if block >= blocks {
+                                break;
+                            }
+                            done.push((block, run_block(block)));
+                        }
+                        done
+                    })
+                })
+                .collect();
+            for worker in workers {
+                for (block, acc) in worker.join().expect("simulation worker panicked") {
+                    results[block] = Some(acc);
+                }
+            }
+        });
+    }
+
+    let mut total = make();
+    for acc in results.into_iter().flatten() {
+        total.merge(acc);
+    }
+    total
+}
+
+/// Runs `config.trials` battles and summarizes their outcomes without
+/// keeping the individual results. Survivors are summed over each side's
+/// participants.
+pub fn simulate_battles_stats(battle: &Battle, config: &MonteCarloConfig) -> BattleStatistics {
+    let acc = run_trials(
+        battle,
+        0..config.trials,
+        config.master_seed(),
+        config.threads,
+        config.engine,
+        config.rng,
+        StatsAccumulator::default,
+    );
+    acc.finish(&config.percentiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::battle;
+    use crate::types::unit_type::UnitType;
+
+    #[test]
+    fn thread_count_does_not_change_the_result() {
+        let battle = battle(
+            &[(UnitType::LightFighter, 120), (UnitType::Cruiser, 8)],
+            &[(UnitType::MissileLauncher, 100), (UnitType::LightLaser, 30)],
+        );
+        let run = |threads| {
+            let stats = simulate_battles_stats(
+                &battle,
+                &MonteCarloConfig {
+                    trials: 1000,
+                    seed: Some(99),
+                    threads,
+                    ..MonteCarloConfig::default()
+                },
+            );
+            serde_json::to_value(stats).unwrap()
+        };
+        let single = run(1);
+        assert_eq!(run(4), single);
+        assert_eq!(run(3), single);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::simulator::loot::plunder;
+use crate::simulator::monte_carlo::{MonteCarloConfig, TrialAccumulator, run_trials};
+use crate::simulator::report::{BattleOutcome, BattleReport, ParticipantReport};
+use crate::types::battle::Battle;
+use crate::types::resources::Resources;
+use crate::types::unit_stats::UNIT_STATS;
+use crate::types::unit_type::UnitType;
+
+/// Market exchange rates, e.g. the common 3:2:1 (3 metal = 2 crystal =
+/// 1 deuterium).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeRatios {
+    pub metal: f64,
+    pub crystal: f64,
+    pub deuterium: f64,
+}
+
+impl Default for TradeRatios {
+    fn default() -> Self {
+        Self {
+            metal: 3.0,
+            crystal: 2.0,
+            deuterium: 1.0,
+        }
+    }
+}
+
+impl TradeRatios {
+    /// Worth of `r` expressed in metal.
+    pub fn metal_value(&self, r: &Resources) -> f64 {
+        r.metal + r.crystal * self.metal / self.crystal + r.deuterium * self.metal / self.deuterium
+    }
+}
+
+/// Everything about a planned attack that the battle itself does not know.
+#[derive(Debug, Clone)]
+pub struct RaidPlan {
+    /// Resources on the target planet.
+    pub planet: Resources,
+    /// Deuterium burnt by the attacking fleets for the round trip.
+    pub fuel: f64,
+    /// Share of the debris field the attacker expects to recycle.
+    pub debris_harvest: f64,
+    pub trade: TradeRatios,
+}
+
+impl Default for RaidPlan {
+    fn default() -> Self {
+        Self {
+            planet: Resources::default(),
+            fuel: 0.0,
+            debris_harvest: 1.0,
+            trade: TradeRatios::default(),
+        }
+    }
+}
+
+/// Expected economics of a raid, averaged over Monte-Carlo trials.
+#[derive(Debug, Clone, Default)]
+pub struct ProfitReport {
+    pub trials: u64,
+    /// Resource value of the attacker's destroyed units.
+    pub attacker_losses: Resources,
+    /// Resource value of the defender's destroyed units, rebuilt defenses
+    /// excluded.
+    pub defender_losses: Resources,
+    pub loot: Resources,
+    /// Harvested part of the debris field.
+    pub debris: Resources,
+    pub fuel: Resources,
+    /// Loot plus harvested debris minus losses and fuel, in metal.
+    pub attacker_net: f64,
+    /// Defender's losses plus the loot taken from them, in metal.
+    pub defender_net_loss: f64,
+}
+
+/// Metal, crystal and deuterium it took to build `units`.
+pub fn resource_value(units: &[u64; UnitType::count()]) -> Resources {
+    let mut total = Resources::default();
+    for (&ut, &n) in UnitType::iter().zip(units) {
+        let stats = UNIT_STATS[ut];
+        total +=
+            Resources::new(stats.metal_cost, stats.crystal_cost, stats.deuterium_cost) * n as f64;
+    }
+    total
+}
+
+pub(crate) fn side_value(
+    participants: &[ParticipantReport],
+    lost: fn(&ParticipantReport) -> [u64; UnitType::count()],
+) -> Resources {
+    participants
+        .iter()
+        .map(|p| resource_value(&lost(p)))
+        .fold(Resources::default(), |a, b| a + b)
+}
+
+/// Running sums of a raid analysis.
+struct ProfitAccumulator<'a> {
+    plan: &'a RaidPlan,
+    plunder: f64,
+    sum: ProfitReport,
+}
+
+impl TrialAccumulator for ProfitAccumulator<'_> {
+    fn add(&mut self, report: &BattleReport) {
+        let sum = &mut self.sum;
+        sum.attacker_losses += side_value(&report.attackers, ParticipantReport::lost);
+        sum.defender_losses += side_value(&report.defenders, ParticipantReport::permanently_lost);
+        if report.outcome() == BattleOutcome::AttackerWin {
+            let survivors = report.attacker_remaining();
+            sum.loot += plunder(&self.plan.planet, self.plunder, &survivors).resources;
+        }
+        sum.debris += Resources::new(report.debris.metal, report.debris.crystal, 0.0)
+            * self.plan.debris_harvest;
+        sum.trials += 1;
+    }
+
+    fn merge(&mut self, other: Self) {
+        let sum = &mut self.sum;
+        sum.attacker_losses += other.sum.attacker_losses;
+        sum.defender_losses += other.sum.defender_losses;
+        sum.loot += other.sum.loot;
+        sum.debris += other.sum.debris;
+        sum.trials += other.sum.trials;
+    }
+}
+
+/// Answers "is this raid worth it": simulates `config.trials` battles and
+/// averages losses, loot, harvestable debris and fuel into a net result.
+pub fn analyze_raid(battle: &Battle, plan: &RaidPlan, config: &MonteCarloConfig) -> ProfitReport {
+    let sum = run_trials(
+        battle,
+        0..config.trials,
+        config.master_seed(),
+        config.threads,
+        config.engine,
+        config.rng,
+        || ProfitAccumulator {
+            plan,
+            plunder: battle.universe.plunder,
+            sum: ProfitReport::default(),
+        },
+    )
+    .sum;
+
+    let inv = if sum.trials > 0 {
+        1.0 / sum.trials as f64
+    } else {
+        0.0
+    };
+    let attacker_losses = sum.attacker_losses * inv;
+    let defender_losses = sum.defender_losses * inv;
+    let loot = sum.loot * inv;
+    let debris = sum.debris * inv;
+    let fuel = Resources::new(0.0, 0.0, plan.fuel);
+    let trade = &plan.trade;
+    ProfitReport {
+        trials: sum.trials,
+        attacker_losses,
+        defender_losses,
+        loot,
+        debris,
+        fuel,
+        attacker_net: trade.metal_value(&loot) + trade.metal_value(&debris)
+            - trade.metal_value(&attacker_losses)
+            - trade.metal_value(&fuel),
+        defender_net_loss: trade.metal_value(&defender_losses) + trade.metal_value(&loot),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::battle;
+
+    fn counts(units: &[(UnitType, u64)]) -> [u64; UnitType::count()] {
+        let mut counts = [0; UnitType::count()];
+        for &(ut, n) in units {
+            counts[ut as usize] = n;
+        }
+        counts
+    }
+
+    fn config() -> MonteCarloConfig {
+        MonteCarloConfig {
+            trials: 50,
+            seed: Some(5),
+            ..MonteCarloConfig::default()
+        }
+    }
+
+    #[test]
+    fn values_resources_in_metal() {
+        let trade = TradeRatios::default();
+        assert_eq!(
+            trade.metal_value(&Resources::new(100.0, 100.0, 100.0)),
+            550.0
+        );
+        let even = TradeRatios {
+            metal: 1.0,
+            crystal: 1.0,
+            deuterium: 1.0,
+        };
+        assert_eq!(even.metal_value(&Resources::new(1.0, 2.0, 3.0)), 6.0);
+    }
+
+    #[test]
+    fn values_units_at_their_build_cost() {
+        assert_eq!(
+            resource_value(&counts(&[
+                (UnitType::LightFighter, 2),
+                (UnitType::Cruiser, 1)
+            ])),
+            Resources::new(26_000.0, 9_000.0, 2_000.0)
+        );
+        assert_eq!(
+            resource_value(&[0; UnitType::count()]),
+            Resources::default()
+        );
+    }
+
+    #[test]
+    fn a_won_raid_counts_loot_and_debris() {
+        // the satellite's shots bounce off the battleships' shields
+        let battle = battle(
+            &[(UnitType::Battleship, 100)],
+            &[(UnitType::SolarSatellite, 1)],
+        );
+        let plan = RaidPlan {
+            planet: Resources::new(30_000.0, 20_000.0, 10_000.0),
+            fuel: 1_000.0,
+            ..RaidPlan::default()
+        };
+        let report = analyze_raid(&battle, &plan, &config());
+        assert_eq!(report.trials, 50);
+        assert_eq!(report.attacker_losses, Resources::default());
+        assert_eq!(report.defender_losses, Resources::new(0.0, 2_000.0, 500.0));
+        assert_eq!(report.loot, Resources::new(15_000.0, 10_000.0, 5_000.0));
+        assert_eq!(report.debris, Resources::new(0.0, 600.0, 0.0));
+        assert_eq!(report.fuel, Resources::new(0.0, 0.0, 1_000.0));
+        // 45 000 loot + 900 debris - 3 000 fuel
+        assert_eq!(report.attacker_net, 42_900.0);
+        // 4 500 satellite + 45 000 loot
+        assert_eq!(report.defender_net_loss, 49_500.0);
+    }
+
+    #[test]
+    fn a_lost_raid_takes_no_loot() {
+        let battle = battle(
+            &[(UnitType::LightFighter, 1)],
+            &[(UnitType::PlasmaTurret, 100)],
+        );
+        let plan = RaidPlan {
+            planet: Resources::new(30_000.0, 20_000.0, 10_000.0),
+            ..RaidPlan::default()
+        };
+        let report = analyze_raid(&battle, &plan, &config());
+        assert_eq!(report.loot, Resources::default());
+        assert_eq!(report.defender_losses, Resources::default());
+        assert_eq!(
+            report.attacker_losses,
+            Resources::new(3_000.0, 1_000.0, 0.0)
+        );
+        assert_eq!(report.debris, Resources::new(900.0, 300.0, 0.0));
+        // the fighter's 4 500 less the 1 350 recycled from its own wreck
+        assert_eq!(report.attacker_net, -3_150.0);
+        assert_eq!(report.defender_net_loss, 0.0);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::json::unit_counts;
+use crate::simulator::debris::DebrisField;
+use crate::types::unit_type::UnitType;
+use serde::Serialize;
+
+/// Firing totals of one side during one round, as shown in the combat report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct FireStats {
+    /// Shots fired, rapid-fire follow-ups included.
+    pub shots: u64,
+    /// Total firepower of those shots.
+    pub damage: f64,
+    /// Part of `damage` soaked up by the targets' shields.
+    pub absorbed: f64,
+    /// Shots that did nothing because of the shield bounce rule.
+    pub bounced: u64,
+}
+
+/// Who won a battle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum BattleOutcome {
+    AttackerWin,
+    DefenderWin,
+    /// Both sides survived the last round, or both were wiped out.
+    Draw,
+}
+
+/// State of both sides at the end of one round.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundReport {
+    /// Surviving units of each attacking participant.
+    #[serde(serialize_with = "unit_counts::serialize_each")]
+    pub attackers: Vec<[u64; UnitType::count()]>,
+    /// Surviving units of each defending participant.
+    #[serde(serialize_with = "unit_counts::serialize_each")]
+    pub defenders: Vec<[u64; UnitType::count()]>,
+    /// Shots of the attacking fleet on the defender.
+    pub attacker_fire: FireStats,
+    /// Shots of the defending fleet on the attacker.
+    pub defender_fire: FireStats,
+}
+
+impl RoundReport {
+    pub fn attacker_units(&self) -> [u64; UnitType::count()] {
+        sum_counts(self.attackers.iter())
+    }
+
+    pub fn defender_units(&self) -> [u64; UnitType::count()] {
+        sum_counts(self.defenders.iter())
+    }
+}
+
+/// Fleet of one participant before and after the battle.
+#[derive(Debug, Clone, Serialize)]
+pub struct ParticipantReport {
+    /// Units that entered the battle; missiles never do.
+    #[serde(serialize_with = "unit_counts::serialize")]
+    pub initial: [u64; UnitType::count()],
+    #[serde(serialize_with = "unit_counts::serialize")]
+    pub remaining: [u64; UnitType::count()],
+    /// Destroyed defensive structures that were rebuilt after the battle;
+    /// always zero for ships and for attacking participants.
+    #[serde(serialize_with = "unit_counts::serialize")]
+    pub rebuilt: [u64; UnitType::count()],
+}
+
+impl ParticipantReport {
+    /// Units destroyed per type during the battle, rebuilt defenses included.
+    pub fn lost(&self) -> [u64; UnitType::count()] {
+        std::array::from_fn(|i| self.initial[i] - self.remaining[i])
+    }
+
+    /// Units gone for good once rebuilt defenses are back in place.
+    pub fn permanently_lost(&self) -> [u64; UnitType::count()] {
+        std::array::from_fn(|i| self.initial[i] - self.remaining[i] - self.rebuilt[i])
+    }
+}
+
+/// Full outcome of a single battle.
+///
+/// `rounds` is only filled by the `_detailed` entry points; the others leave
+/// it empty.
+#[derive(Debug, Clone, Serialize)]
+pub struct BattleReport {
+    /// One entry per attacking participant, in input order.
+    pub attackers: Vec<ParticipantReport>,
+    /// One entry per defending participant, in input order.
+    pub defenders: Vec<ParticipantReport>,
+    pub rounds: Vec<RoundReport>,
+    /// Rounds actually fought. The battle stops right after the round that
+    /// wipes out a side, so for a decided battle this is the deciding round.
+    pub rounds_fought: usize,
+    /// Debris left by the losses of both sides.
+    pub debris: DebrisField,
+    /// Chance in percent that the debris field creates a moon.
+    pub moon_chance: u32,
+}
+
+impl BattleReport {
+    pub fn outcome(&self) -> BattleOutcome {
+        let attacker_alive = self.attacker_remaining().iter().any(|&n| n > 0);
+        let defender_alive = self.defender_remaining().iter().any(|&n| n > 0);
+        match (attacker_alive, defender_alive) {
+            (true, false) => BattleOutcome::AttackerWin,
+            (false, true) => BattleOutcome::DefenderWin,
+            _ => BattleOutcome::Draw,
+        }
+    }
+
+    /// Surviving units of the whole attacking side.
+    pub fn attacker_remaining(&self) -> [u64; UnitType::count()] {
+        sum_counts(self.attackers.iter().map(|p| &p.remaining))
+    }
+
+    /// Surviving units of the whole defending side.
+    pub fn defender_remaining(&self) -> [u64; UnitType::count()] {
+        sum_counts(self.defenders.iter().map(|p| &p.remaining))
+    }
+}
+
+fn sum_counts<'a>(
+    counts: impl Iterator<Item = &'a [u64; UnitType::count()]>,
+) -> [u64; UnitType::count()] {
+    let mut total = [0u64; UnitType::count()];
+    for c in counts {
+        for (t, &n) in total.iter_mut().zip(c) {
+            *t += n;
+        }
+    }
+    total
+}
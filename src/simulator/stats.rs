@@ -0,0 +1,408 @@
+use crate::json::unit_counts;
+use crate::simulator::debris::DebrisField;
+use crate::simulator::monte_carlo::TrialAccumulator;
+use crate::simulator::profit::side_value;
+use crate::simulator::report::{BattleOutcome, BattleReport, ParticipantReport};
+use crate::types::unit_type::UnitType;
+use serde::Serialize;
+
+/// Distribution of one unit type's survivor count across trials.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UnitSummary {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: u64,
+    pub max: u64,
+    /// `(percentile, value)` pairs in the order requested by the caller;
+    /// exact below 1024 survivors, within 0.2 % of `max` above.
+    pub percentiles: Vec<(f64, u64)>,
+}
+
+/// Aggregate of a Monte-Carlo run.
+#[derive(Debug, Clone, Serialize)]
+pub struct BattleStatistics {
+    pub trials: u64,
+    pub attacker_win: f64,
+    pub defender_win: f64,
+    pub draw: f64,
+    #[serde(serialize_with = "unit_counts::serialize_summaries")]
+    pub attacker: [UnitSummary; UnitType::count()],
+    #[serde(serialize_with = "unit_counts::serialize_summaries")]
+    pub defender: [UnitSummary; UnitType::count()],
+    /// `rounds[n]` = number of trials that lasted exactly `n` rounds.
+    pub rounds: Vec<u64>,
+    /// Same as `rounds`, for the trials the attacker won.
+    pub attacker_win_rounds: Vec<u64>,
+    /// Same as `rounds`, for the trials the defender won.
+    pub defender_win_rounds: Vec<u64>,
+    /// Mean number of rounds fought.
+    pub expected_rounds: f64,
+    /// Mean debris field over all trials.
+    pub expected_debris: DebrisField,
+    /// Mean number of destroyed defenses rebuilt on the defending side.
+    #[serde(serialize_with = "unit_counts::serialize_means")]
+    pub expected_rebuilt: [f64; UnitType::count()],
+    /// `moon_chances[p]` = number of trials that gave a `p` % moon chance.
+    pub moon_chances: Vec<u64>,
+    /// Probability of getting a moon, over both the battle and the moon roll.
+    pub expected_moon_chance: f64,
+    /// Mean resources (metal + crystal + deuterium) lost by the attacker.
+    pub expected_attacker_losses: f64,
+    /// Same for the defender, rebuilt defenses excluded.
+    pub expected_defender_losses: f64,
+}
+
+/// Exact running mean and variance of a whole-numbered quantity.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Moments {
+    n: u64,
+    sum: u128,
+    sum_sq: u128,
+}
+
+impl Moments {
+    pub(crate) fn add(&mut self, value: u64) {
+        self.n += 1;
+        self.sum += value as u128;
+        self.sum_sq += value as u128 * value as u128;
+    }
+
+    pub(crate) fn merge(&mut self, other: &Self) {
+        self.n += other.n;
+        self.sum += other.sum;
+        self.sum_sq += other.sum_sq;
+    }
+
+    pub(crate) fn mean(&self) -> f64 {
+        if self.n == 0 {
+            return 0.0;
+        }
+        self.sum as f64 / self.n as f64
+    }
+
+    pub(crate) fn variance(&self) -> f64 {
+        if self.n == 0 {
+            return 0.0;
+        }
+        let n = self.n as f64;
+        let mean = self.sum as f64 / n;
+        f64::max(0.0, self.sum_sq as f64 / n - mean * mean)
+    }
+
+    /// Half-width of the normal-approximation confidence interval of the
+    /// mean, `z` standard errors wide.
+    pub(crate) fn half_width(&self, z: f64) -> f64 {
+        if self.n == 0 {
+            return f64::INFINITY;
+        }
+        z * (self.variance() / self.n as f64).sqrt()
+    }
+}
+
+/// Most bins a [`CountAccumulator`] histogram grows to.
+const HISTOGRAM_BINS: usize = 1024;
+
+/// Streaming moments, extremes and a bounded histogram of one counter.
+///
+/// The histogram has at most [`HISTOGRAM_BINS`] bins of equal width; the
+/// width starts at one and doubles whenever a value falls past the last bin.
+/// Percentiles are therefore exact while the counter stays below
+/// `HISTOGRAM_BINS`, and otherwise off by less than one bin, under 0.2 % of
+/// the largest value seen. Two accumulators merge without further loss.
+#[derive(Debug, Clone)]
+pub(crate) struct CountAccumulator {
+    pub(crate) moments: Moments,
+    min: u64,
+    max: u64,
+    /// Each bin covers `width` consecutive values; always a power of two.
+    width: u64,
+    bins: Vec<u64>,
+}
+
+impl Default for CountAccumulator {
+    fn default() -> Self {
+        Self {
+            moments: Moments::default(),
+            min: u64::MAX,
+            max: 0,
+            width: 1,
+            bins: Vec::new(),
+        }
+    }
+}
+
+impl CountAccumulator {
+    pub(crate) fn add(&mut self, value: u64) {
+        self.moments.add(value);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        while value / self.width >= HISTOGRAM_BINS as u64 {
+            self.widen();
+        }
+        count_into(&mut self.bins, (value / self.width) as usize);
+    }
+
+    /// Doubles the bin width, folding every pair of bins into one.
+    fn widen(&mut self) {
+        self.bins = self.bins.chunks(2).map(|pair| pair.iter().sum()).collect();
+        self.width *= 2;
+    }
+
+    pub(crate) fn merge(&mut self, other: &Self) {
+        self.moments.merge(&other.moments);
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        while self.width < other.width {
+            self.widen();
+        }
+        // both widths are powers of two, so each of `other`'s bins falls
+        // entirely inside one of ours
+        let ratio = (self.width / other.width) as usize;
+        let len = other.bins.len().div_ceil(ratio);
+        if self.bins.len() < len {
+            self.bins.resize(len, 0);
+        }
+        for (i, &count) in other.bins.iter().enumerate() {
+            self.bins[i / ratio] += count;
+        }
+    }
+
+    /// Nearest-rank percentile, `p` in percent.
+    pub(crate) fn percentile(&self, p: f64) -> u64 {
+        let n = self.moments.n;
+        if n == 0 {
+            return 0;
+        }
+        let rank = ((p / 100.0 * n as f64).ceil() as u64).max(1).min(n);
+        let mut seen = 0;
+        for (i, &count) in self.bins.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return (i as u64 * self.width).clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+
+    pub(crate) fn summary(&self, percentiles: &[f64]) -> UnitSummary {
+        let empty = self.moments.n == 0;
+        UnitSummary {
+            mean: self.moments.mean(),
+            std_dev: self.moments.variance().sqrt(),
+            min: if empty { 0 } else { self.min },
+            max: self.max,
+            percentiles: percentiles
+                .iter()
+                .map(|&p| (p, self.percentile(p)))
+                .collect(),
+        }
+    }
+}
+
+/// Running totals of a Monte-Carlo run.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StatsAccumulator {
+    pub(crate) trials: u64,
+    pub(crate) attacker_wins: u64,
+    defender_wins: u64,
+    pub(crate) attacker: [CountAccumulator; UnitType::count()],
+    pub(crate) defender: [CountAccumulator; UnitType::count()],
+    rounds: Vec<u64>,
+    attacker_win_rounds: Vec<u64>,
+    defender_win_rounds: Vec<u64>,
+    debris_metal: u128,
+    debris_crystal: u128,
+    rebuilt: [u64; UnitType::count()],
+    moon_chances: Vec<u64>,
+    pub(crate) attacker_losses: Moments,
+    pub(crate) defender_losses: Moments,
+}
+
+impl TrialAccumulator for StatsAccumulator {
+    fn add(&mut self, report: &BattleReport) {
+        self.trials += 1;
+        match report.outcome() {
+            BattleOutcome::AttackerWin => {
+                self.attacker_wins += 1;
+                count_into(&mut self.attacker_win_rounds, report.rounds_fought);
+            }
+            BattleOutcome::DefenderWin => {
+                self.defender_wins += 1;
+                count_into(&mut self.defender_win_rounds, report.rounds_fought);
+            }
+            BattleOutcome::Draw => {}
+        }
+        for (acc, &n) in self.attacker.iter_mut().zip(&report.attacker_remaining()) {
+            acc.add(n);
+        }
+        for (acc, &n) in self.defender.iter_mut().zip(&report.defender_remaining()) {
+            acc.add(n);
+        }
+        count_into(&mut self.rounds, report.rounds_fought);
+        // single-battle debris is always whole, so the sums stay exact
+        self.debris_metal += report.debris.metal as u128;
+        self.debris_crystal += report.debris.crystal as u128;
+        count_into(&mut self.moon_chances, report.moon_chance as usize);
+        let attacker_lost = side_value(&report.attackers, ParticipantReport::lost);
+        let defender_lost = side_value(&report.defenders, ParticipantReport::permanently_lost);
+        self.attacker_losses.add(attacker_lost.total() as u64);
+        self.defender_losses.add(defender_lost.total() as u64);
+        for defender in &report.defenders {
+            for (sum, &n) in self.rebuilt.iter_mut().zip(&defender.rebuilt) {
+                *sum += n;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.trials += other.trials;
+        self.attacker_wins += other.attacker_wins;
+        self.defender_wins += other.defender_wins;
+        for (acc, o) in self.attacker.iter_mut().zip(&other.attacker) {
+            acc.merge(o);
+        }
+        for (acc, o) in self.defender.iter_mut().zip(&other.defender) {
+            acc.merge(o);
+        }
+        add_histogram(&mut self.rounds, &other.rounds);
+        add_histogram(&mut self.attacker_win_rounds, &other.attacker_win_rounds);
+        add_histogram(&mut self.defender_win_rounds, &other.defender_win_rounds);
+        self.debris_metal += other.debris_metal;
+        self.debris_crystal += other.debris_crystal;
+        for (sum, &n) in self.rebuilt.iter_mut().zip(&other.rebuilt) {
+            *sum += n;
+        }
+        add_histogram(&mut self.moon_chances, &other.moon_chances);
+        self.attacker_losses.merge(&other.attacker_losses);
+        self.defender_losses.merge(&other.defender_losses);
+    }
+}
+
+impl StatsAccumulator {
+    pub(crate) fn finish(&self, percentiles: &[f64]) -> BattleStatistics {
+        let inv = if self.trials > 0 {
+            1.0 / self.trials as f64
+        } else {
+            0.0
+        };
+        let attacker_win = self.attacker_wins as f64 * inv;
+        let defender_win = self.defender_wins as f64 * inv;
+        BattleStatistics {
+            trials: self.trials,
+            attacker_win,
+            defender_win,
+            draw: if self.trials > 0 {
+                1.0 - attacker_win - defender_win
+            } else {
+                0.0
+            },
+            attacker: std::array::from_fn(|i| self.attacker[i].summary(percentiles)),
+            defender: std::array::from_fn(|i| self.defender[i].summary(percentiles)),
+            rounds: self.rounds.clone(),
+            attacker_win_rounds: self.attacker_win_rounds.clone(),
+            defender_win_rounds: self.defender_win_rounds.clone(),
+            expected_rounds: self
+                .rounds
+                .iter()
+                .enumerate()
+                .map(|(n, &count)| n as f64 * count as f64)
+                .sum::<f64>()
+                * inv,
+            expected_debris: DebrisField {
+                metal: self.debris_metal as f64 * inv,
+                crystal: self.debris_crystal as f64 * inv,
+            },
+            expected_rebuilt: std::array::from_fn(|i| self.rebuilt[i] as f64 * inv),
+            moon_chances: self.moon_chances.clone(),
+            expected_moon_chance: self
+                .moon_chances
+                .iter()
+                .enumerate()
+                .map(|(p, &n)| p as f64 / 100.0 * n as f64)
+                .sum::<f64>()
+                * inv,
+            expected_attacker_losses: self.attacker_losses.mean(),
+            expected_defender_losses: self.defender_losses.mean(),
+        }
+    }
+}
+
+/// Counts one observation of `value` in a dense histogram.
+fn count_into(histogram: &mut Vec<u64>, value: usize) {
+    if histogram.len() <= value {
+        histogram.resize(value + 1, 0);
+    }
+    histogram[value] += 1;
+}
+
+fn add_histogram(into: &mut Vec<u64>, from: &[u64]) {
+    if into.len() < from.len() {
+        into.resize(from.len(), 0);
+    }
+    for (a, &b) in into.iter_mut().zip(from) {
+        *a += b;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(values: impl IntoIterator<Item = u64>) -> CountAccumulator {
+        let mut acc = CountAccumulator::default();
+        for v in values {
+            acc.add(v);
+        }
+        acc
+    }
+
+    #[test]
+    fn small_counts_give_exact_percentiles() {
+        let acc = filled(1..=100);
+        assert_eq!(acc.percentile(5.0), 5);
+        assert_eq!(acc.percentile(50.0), 50);
+        assert_eq!(acc.percentile(100.0), 100);
+        let summary = acc.summary(&[25.0]);
+        assert_eq!((summary.min, summary.max), (1, 100));
+        assert_eq!(summary.percentiles, [(25.0, 25)]);
+    }
+
+    #[test]
+    fn histogram_stays_bounded() {
+        // 20 000 distinct survivor counts spread over 0..2 000 000
+        let acc = filled((0..20_000).map(|i| i * 100));
+        assert!(acc.bins.len() <= HISTOGRAM_BINS);
+        let max = acc.summary(&[]).max;
+        assert_eq!(max, 1_999_900);
+        for p in [1.0, 25.0, 50.0, 95.0] {
+            let exact = ((p / 100.0 * 20_000.0) as u64 - 1) * 100;
+            let estimate = acc.percentile(p);
+            assert!(
+                exact.abs_diff(estimate) <= max / 500,
+                "{p}: {estimate} vs {exact}"
+            );
+        }
+    }
+
+    #[test]
+    fn merging_matches_adding_in_one_go() {
+        let values: Vec<u64> = (0..5000).map(|i| (i * 7919) % 3001).collect();
+        let whole = filled(values.iter().copied().chain([250_000]));
+        let mut merged = filled(values[..1000].iter().copied());
+        merged.merge(&filled(values[1000..].iter().copied().chain([250_000])));
+        assert_eq!(merged.width, whole.width);
+        assert_eq!(merged.bins, whole.bins);
+        assert_eq!(merged.summary(&[10.0, 90.0]), whole.summary(&[10.0, 90.0]));
+    }
+
+    #[test]
+    fn empty_counter_summarises_to_zero() {
+        assert_eq!(
+            CountAccumulator::default().summary(&[50.0]),
+            UnitSummary {
+                percentiles: vec![(50.0, 0)],
+                ..UnitSummary::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,24 @@
+//! Battle builders shared by the unit tests.
+
+use crate::types::battle::Battle;
+use crate::types::player::{Fleet, Player, TechLevels};
+use crate::types::unit_type::UnitType;
+
+/// Participant with every research at `level`.
+pub(crate) fn player(units: &[(UnitType, u64)], level: u8) -> Player {
+    Player {
+        fleet: Fleet {
+            units: units.iter().copied().collect(),
+        },
+        tech: TechLevels {
+            weapon: level,
+            shield: level,
+            armor: level,
+        },
+    }
+}
+
+/// One attacker against one defender, research 0 on both sides.
+pub(crate) fn battle(attacker: &[(UnitType, u64)], defender: &[(UnitType, u64)]) -> Battle {
+    Battle::new(player(attacker, 0), player(defender, 0))
+}
@@ -0,0 +1,27 @@
+use crate::types::player::Player;
+use crate::types::universe::UniverseSettings;
+use serde::{Deserialize, Serialize};
+
+/// Input of a battle between two sides of one or more participants each.
+///
+/// A plain attack is one attacker against one defender; alliance combat
+/// (ACS attack / ACS defend) adds further players to either side, each
+/// fighting with their own research.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Battle {
+    pub attackers: Vec<Player>,
+    pub defenders: Vec<Player>,
+    #[serde(default)]
+    pub universe: UniverseSettings,
+}
+
+impl Battle {
+    /// One attacker against one defender.
+    pub fn new(attacker: Player, defender: Player) -> Self {
+        Self {
+            attackers: vec![attacker],
+            defenders: vec![defender],
+            universe: UniverseSettings::default(),
+        }
+    }
+}
@@ -1,10 +1,10 @@
-// 1) Declare the two submodules:
+pub mod battle;
 pub mod player;
+pub mod resources;
 pub mod unit;
 pub mod unit_rapid_fire;
 pub mod unit_stats;
 pub mod unit_type;
-#[allow(unused)]
+pub mod universe;
 pub use unit_rapid_fire::{RAPID_FIRE, rapid_fire_for};
-#[allow(unused)]
 pub use unit_stats::{UNIT_STATS, UnitStats, stats_for};
@@ -1,22 +1,27 @@
 use crate::types::unit_type::UnitType;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
-#[allow(unused)]
+/// Unit counts of one participant, keyed by type. In JSON, an object from
+/// unit type name to count.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Fleet {
     pub units: HashMap<UnitType, u64>,
 }
 
-#[derive(Debug, Clone)]
-#[allow(unused)]
+/// Combat research levels applied to every unit of a participant.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TechLevels {
     pub weapon: u8,
     pub shield: u8,
     pub armor: u8,
 }
 
-#[derive(Debug, Clone)]
-#[allow(unused)]
+/// One side's battle input: its fleet and research.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Player {
     pub fleet: Fleet,
     pub tech: TechLevels,
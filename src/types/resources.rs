@@ -0,0 +1,47 @@
+use std::ops::{Add, AddAssign, Mul};
+
+/// An amount of metal, crystal and deuterium.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Resources {
+    pub metal: f64,
+    pub crystal: f64,
+    pub deuterium: f64,
+}
+
+impl Resources {
+    pub fn new(metal: f64, crystal: f64, deuterium: f64) -> Self {
+        Self {
+            metal,
+            crystal,
+            deuterium,
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.metal + self.crystal + self.deuterium
+    }
+}
+
+impl Add for Resources {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(
+            self.metal + rhs.metal,
+            self.crystal + rhs.crystal,
+            self.deuterium + rhs.deuterium,
+        )
+    }
+}
+
+impl AddAssign for Resources {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Mul<f64> for Resources {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self {
+        Self::new(self.metal * rhs, self.crystal * rhs, self.deuterium * rhs)
+    }
+}
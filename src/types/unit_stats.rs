@@ -14,7 +14,6 @@ pub struct UnitStats {
     pub speed: f64, // in units/hour
     pub cargo: f64, // in resource units
 }
-#[allow(unused)]
 pub static UNIT_STATS: Lazy<[UnitStats; UnitType::count()]> = Lazy::new(|| {
     [
         // 0 SmallCargo
@@ -103,7 +102,7 @@ pub static UNIT_STATS: Lazy<[UnitStats; UnitType::count()]> = Lazy::new(|| {
             crystal_cost: 6000.0,
             deuterium_cost: 0.0,
             speed: 2000.0,
-            cargo: 2000.0,
+            cargo: 20000.0,
         },
         // 8 Espionage Probe
         UnitStats {
@@ -324,7 +323,6 @@ impl Index<UnitType> for [UnitStats; UnitType::count()] {
         &self[unit as usize]
     }
 }
-#[allow(unused)]
 /// Convenience accessor
 pub fn stats_for(unit: UnitType) -> &'static UnitStats {
     &UNIT_STATS[unit]
@@ -1,7 +1,11 @@
 // src/types/unit_type.rs
 
 use core::slice;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
 const N: usize = 27;
 /// All the unit types in OGame
@@ -81,6 +85,48 @@ impl UnitType {
         Self::ALL.iter()
     }
 
+    /// Defensive structures, `MissileLauncher` through `LargeShieldDome`.
+    pub const fn is_defense(self) -> bool {
+        (self as u8) >= (UnitType::MissileLauncher as u8)
+            && (self as u8) <= (UnitType::LargeShieldDome as u8)
+    }
+
+    /// Anti-ballistic and interplanetary missiles, which sit in a planet's
+    /// defense but never take part in a battle.
+    pub const fn is_missile(self) -> bool {
+        (self as u8) >= (UnitType::AntiBallisticMissiles as u8)
+    }
+
+    /// Variant name, e.g. `"LightFighter"`.
+    pub const fn name(self) -> &'static str {
+        NAMES[self as usize]
+    }
+
+    /// Name shown in the English game client, e.g. `"Rocket Launcher"` for
+    /// `MissileLauncher`.
+    pub const fn ingame_name(self) -> &'static str {
+        INGAME_NAMES[self as usize]
+    }
+
+    /// Looks a unit up by its English in-game name, ignoring case, spaces
+    /// and punctuation.
+    pub fn from_ingame_name(name: &str) -> Option<Self> {
+        let wanted = fold_name(name);
+        UnitType::iter()
+            .find(|ut| fold_name(ut.ingame_name()) == wanted)
+            .copied()
+    }
+
+    /// The game's numeric technology id, e.g. `204` for `LightFighter`.
+    pub const fn tech_id(self) -> u16 {
+        TECH_IDS[self as usize]
+    }
+
+    /// Looks a unit up by its technology id.
+    pub fn from_tech_id(id: u16) -> Option<Self> {
+        UnitType::iter().find(|ut| ut.tech_id() == id).copied()
+    }
+
     /// Try to convert a `u8` into a `UnitType`
     pub fn from_u8(n: u8) -> Option<Self> {
         // Safe because repr(u8) and ALL is ordered
@@ -93,6 +139,144 @@ impl UnitType {
     }
 }
 
+const NAMES: [&str; N] = [
+    "SmallCargo",
+    "LargeCargo",
+    "LightFighter",
+    "HeavyFighter",
+    "Cruiser",
+    "Battleship",
+    "ColonyShip",
+    "Recycler",
+    "EspionageProbe",
+    "Bomber",
+    "SolarSatellite",
+    "Destroyer",
+    "DeathStar",
+    "BattleCruiser",
+    "Reaper",
+    "PathFinder",
+    "Crawler",
+    "MissileLauncher",
+    "LightLaser",
+    "HeavyLaser",
+    "GaussCannon",
+    "IonCannon",
+    "PlasmaTurret",
+    "SmallShieldDome",
+    "LargeShieldDome",
+    "AntiBallisticMissiles",
+    "InterplanetaryMissiles",
+];
+
+const INGAME_NAMES: [&str; N] = [
+    "Small Cargo",
+    "Large Cargo",
+    "Light Fighter",
+    "Heavy Fighter",
+    "Cruiser",
+    "Battleship",
+    "Colony Ship",
+    "Recycler",
+    "Espionage Probe",
+    "Bomber",
+    "Solar Satellite",
+    "Destroyer",
+    "Deathstar",
+    "Battlecruiser",
+    "Reaper",
+    "Pathfinder",
+    "Crawler",
+    "Rocket Launcher",
+    "Light Laser",
+    "Heavy Laser",
+    "Gauss Cannon",
+    "Ion Cannon",
+    "Plasma Turret",
+    "Small Shield Dome",
+    "Large Shield Dome",
+    "Anti-Ballistic Missiles",
+    "Interplanetary Missiles",
+];
+
+const TECH_IDS: [u16; N] = [
+    202, 203, 204, 205, 206, 207, 208, 209, 210, 211, 212, 213, 214, 215, 218, 219,
+    217, // ships
+    401, 402, 403, 404, 405, 406, 407, 408, // defenses
+    502, 503, // missiles
+];
+
+/// Lower-cased alphanumerics only.
+fn fold_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl fmt::Display for UnitType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Error of parsing a [`UnitType`] from an unknown name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownUnitType(pub String);
+
+impl fmt::Display for UnknownUnitType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown unit type `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownUnitType {}
+
+/// Parses a variant name, ignoring case, spaces, `_` and `-`, so
+/// `LightFighter`, `light_fighter` and `Light Fighter` all work.
+impl FromStr for UnitType {
+    type Err = UnknownUnitType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let wanted = s
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .collect::<String>();
+        UnitType::iter()
+            .find(|ut| ut.name().eq_ignore_ascii_case(&wanted))
+            .copied()
+            .ok_or_else(|| UnknownUnitType(s.to_string()))
+    }
+}
+
+/// Serialized as its variant name, so it can key a JSON object.
+impl Serialize for UnitType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+/// Accepts every spelling [`FromStr`] does.
+impl<'de> Deserialize<'de> for UnitType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NameVisitor;
+
+        impl Visitor<'_> for NameVisitor {
+            type Value = UnitType;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a unit type name")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<UnitType, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(NameVisitor)
+    }
+}
+
 // Allow easy casts back to u8, usize, etc.
 impl From<UnitType> for u8 {
     fn from(u: UnitType) -> u8 {
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Server-side settings that shape how a battle is fought and what it leaves
+/// behind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UniverseSettings {
+    /// Share of the metal and crystal cost of destroyed ships that ends up in
+    /// the debris field (0.3 on most servers).
+    #[serde(deserialize_with = "share")]
+    pub fleet_debris: f64,
+    /// Same for destroyed defensive structures (0.0 on most servers).
+    #[serde(deserialize_with = "share")]
+    pub defense_debris: f64,
+    /// Chance that each destroyed defensive structure is rebuilt for free
+    /// after the battle.
+    #[serde(deserialize_with = "share")]
+    pub defense_rebuild: f64,
+    /// Share of the defender's resources a victorious attacker may take.
+    #[serde(deserialize_with = "share")]
+    pub plunder: f64,
+    pub moon: MoonRules,
+    pub combat: CombatRules,
+}
+
+/// Rules of the fight itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CombatRules {
+    /// Rounds fought before the battle ends in a draw.
+    pub rounds: usize,
+    /// A shot no stronger than this share of the target's full shield
+    /// bounces off while the shield is up.
+    #[serde(deserialize_with = "share")]
+    pub bounce_threshold: f64,
+    /// A hit unit whose hull drops below this share of its full hull may
+    /// explode.
+    #[serde(deserialize_with = "share")]
+    pub explosion_threshold: f64,
+    pub rapid_fire: bool,
+    /// Whether defensive structures fire back; they can be hit either way.
+    pub defenses_shoot: bool,
+}
+
+impl CombatRules {
+    /// The rules of the current live game.
+    pub fn live() -> Self {
+        Self {
+            rounds: 6,
+            bounce_threshold: 0.01,
+            explosion_threshold: 0.7,
+            rapid_fire: true,
+            defenses_shoot: true,
+        }
+    }
+
+    /// Live rules on a universe with rapid fire switched off.
+    pub fn no_rapid_fire() -> Self {
+        Self {
+            rapid_fire: false,
+            ..Self::live()
+        }
+    }
+
+    /// Live rules on a universe where defensive structures only soak up
+    /// shots.
+    pub fn defenses_dont_shoot() -> Self {
+        Self {
+            defenses_shoot: false,
+            ..Self::live()
+        }
+    }
+}
+
+/// Reads a share, rejecting anything outside `0..=1`.
+fn share<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = f64::deserialize(deserializer)?;
+    if (0.0..=1.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err(serde::de::Error::custom(format_args!(
+            "expected a share between 0 and 1, got {value}"
+        )))
+    }
+}
+
+impl Default for CombatRules {
+    fn default() -> Self {
+        Self::live()
+    }
+}
+
+/// How a debris field turns into a chance to create a moon.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MoonRules {
+    /// Debris (metal + crystal) worth one percent of moon chance.
+    pub debris_per_percent: f64,
+    /// Upper bound of the chance, in percent.
+    pub max_chance: u32,
+}
+
+impl Default for MoonRules {
+    fn default() -> Self {
+        Self {
+            debris_per_percent: 100_000.0,
+            max_chance: 20,
+        }
+    }
+}
+
+impl Default for UniverseSettings {
+    fn default() -> Self {
+        Self {
+            fleet_debris: 0.3,
+            defense_debris: 0.0,
+            defense_rebuild: 0.7,
+            plunder: 0.5,
+            moon: MoonRules::default(),
+            combat: CombatRules::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_differ_from_live_in_one_rule() {
+        let live = CombatRules::live();
+        assert!(!CombatRules::no_rapid_fire().rapid_fire);
+        assert!(!CombatRules::defenses_dont_shoot().defenses_shoot);
+        assert_eq!(
+            CombatRules {
+                defenses_shoot: true,
+                ..CombatRules::defenses_dont_shoot()
+            },
+            live
+        );
+    }
+
+    #[test]
+    fn universe_shares_must_be_shares() {
+        let settings: UniverseSettings =
+            serde_json::from_str(r#"{"defense_rebuild": 1.0, "plunder": 0.75}"#).unwrap();
+        assert_eq!(settings.plunder, 0.75);
+        for json in [
+            r#"{"fleet_debris": 30}"#,
+            r#"{"defense_debris": -0.1}"#,
+            r#"{"defense_rebuild": 7}"#,
+            r#"{"plunder": 1.01}"#,
+        ] {
+            let err = serde_json::from_str::<UniverseSettings>(json).unwrap_err();
+            assert!(err.to_string().contains("between 0 and 1"), "{err}");
+        }
+    }
+
+    #[test]
+    fn thresholds_must_be_shares() {
+        let rules: CombatRules =
+            serde_json::from_str(r#"{"bounce_threshold": 0.0, "explosion_threshold": 1.0}"#)
+                .unwrap();
+        assert_eq!(rules.explosion_threshold, 1.0);
+        for json in [
+            r#"{"bounce_threshold": 1.5}"#,
+            r#"{"bounce_threshold": -0.01}"#,
+            r#"{"explosion_threshold": 70}"#,
+        ] {
+            let err = serde_json::from_str::<CombatRules>(json).unwrap_err();
+            assert!(err.to_string().contains("between 0 and 1"), "{err}");
+        }
+    }
+}
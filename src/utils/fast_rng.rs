@@ -1,46 +1,42 @@
+use crate::utils::rng::BattleRng;
 use rand::{RngCore, SeedableRng, TryRngCore, rngs::OsRng};
 use rand_xoshiro::Xoshiro256PlusPlus;
 
+/// The default backend: xoshiro256++.
 pub struct FastRng {
     rng: Xoshiro256PlusPlus,
 }
 
 impl FastRng {
     pub fn new(seed: Option<u64>) -> Self {
-        let seed = seed.unwrap_or_else(|| OsRng.try_next_u64().unwrap());
+        let seed = seed.unwrap_or_else(os_seed);
+        Self::seed_from_u64(seed)
+    }
+}
+
+impl BattleRng for FastRng {
+    fn seed_from_u64(seed: u64) -> Self {
         Self {
             rng: Xoshiro256PlusPlus::seed_from_u64(seed),
         }
     }
 
     #[inline(always)]
-    #[allow(dead_code)]
-    pub fn next(&mut self, end: u64) -> u64 {
-        debug_assert!(end > 0);
-        self.rng.next_u64() % end
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
     }
+}
 
-    #[inline(always)]
-    #[allow(dead_code)]
-    pub fn next_inclusive(&mut self, end: u64) -> u64 {
-        debug_assert!(end > 0 && end < u64::MAX);
-        self.rng.next_u64() % (end + 1)
-    }
-    #[inline(always)]
-    #[allow(dead_code)]
-    pub fn next_f64(&mut self) -> f64 {
-        // take the top 53 bits of a 64-bit random value
-        let bits = self.rng.next_u64() >> 11; // down to 53 bits
-        // divide by 2^53 as f64
-        bits as f64 / (1u64 << 53) as f64
-    }
+/// A seed drawn from the OS.
+pub(crate) fn os_seed() -> u64 {
+    OsRng.try_next_u64().unwrap()
+}
 
-    /// Uniform in [0.0, 1.0)
-    #[inline(always)]
-    #[allow(dead_code)]
-    pub fn next_f32(&mut self) -> f32 {
-        // take the top 24 bits of a 64-bit random value
-        let bits = (self.rng.next_u64() >> 40) as u32; // down to 24 bits
-        bits as f32 / (1u32 << 24) as f32
-    }
+/// Derives the seed of sub-stream `stream` from a master seed (SplitMix64
+/// finalizer), so per-trial RNGs are decorrelated but fully reproducible.
+pub fn derive_seed(master: u64, stream: u64) -> u64 {
+    let mut z = master ^ stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
@@ -1 +1,4 @@
+pub mod counter_rng;
 pub mod fast_rng;
+pub mod rng;
+pub mod wyrand;
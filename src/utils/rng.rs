@@ -0,0 +1,167 @@
+use crate::utils::fast_rng::derive_seed;
+
+/// Source of every random decision the simulator makes.
+///
+/// Implementors only provide raw 64-bit output and seeding; bounded
+/// integers and floats are derived here the same way for every backend.
+pub trait BattleRng {
+    /// A generator seeded with `seed`.
+    fn seed_from_u64(seed: u64) -> Self
+    where
+        Self: Sized;
+
+    /// The generator for sub-stream `stream` of a master seed, e.g. one
+    /// Monte-Carlo trial. Streams are decorrelated but fully reproducible.
+    fn from_stream(master: u64, stream: u64) -> Self
+    where
+        Self: Sized,
+    {
+        Self::seed_from_u64(derive_seed(master, stream))
+    }
+
+    /// Raw 64 random bits.
+    fn next_u64(&mut self) -> u64;
+
+    /// Uniform in `0..end`, without modulo bias.
+    ///
+    /// Lemire's multiply-shift: the high half of `x * end` is the result,
+    /// and the few `x` that would over-represent some values are rejected.
+    /// The `%` only runs when the low half lands in the rejection zone, which
+    /// for battle-sized bounds almost never happens.
+    #[inline(always)]
+    fn next(&mut self, end: u64) -> u64 {
+        debug_assert!(end > 0);
+        let mut m = self.next_u64() as u128 * end as u128;
+        if (m as u64) < end {
+            let threshold = end.wrapping_neg() % end;
+            while (m as u64) < threshold {
+                m = self.next_u64() as u128 * end as u128;
+            }
+        }
+        (m >> 64) as u64
+    }
+
+    /// Uniform in `0..=end`.
+    #[inline(always)]
+    fn next_inclusive(&mut self, end: u64) -> u64 {
+        debug_assert!(end > 0 && end < u64::MAX);
+        self.next(end + 1)
+    }
+
+    /// Uniform in [0.0, 1.0)
+    #[inline(always)]
+    fn next_f64(&mut self) -> f64 {
+        // take the top 53 bits of a 64-bit random value
+        let bits = self.next_u64() >> 11; // down to 53 bits
+        // divide by 2^53 as f64
+        bits as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in [0.0, 1.0)
+    #[inline(always)]
+    fn next_f32(&mut self) -> f32 {
+        // take the top 24 bits of a 64-bit random value
+        let bits = (self.next_u64() >> 40) as u32; // down to 24 bits
+        bits as f32 / (1u32 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::counter_rng::CounterRng;
+    use crate::utils::fast_rng::FastRng;
+    use crate::utils::wyrand::WyRand;
+
+    const SAMPLES: u64 = 200_000;
+
+    /// Replays a fixed list of raw outputs.
+    struct Scripted(std::vec::IntoIter<u64>);
+
+    impl BattleRng for Scripted {
+        fn seed_from_u64(_: u64) -> Self {
+            unreachable!()
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0.next().expect("script exhausted")
+        }
+    }
+
+    /// Upper 0.1 % point of the chi-square distribution with `df` degrees
+    /// of freedom, by the Wilson–Hilferty approximation.
+    fn chi_square_critical(df: u64) -> f64 {
+        let df = df as f64;
+        let z = 3.09;
+        let a = 2.0 / (9.0 * df);
+        df * (1.0 - a + z * a.sqrt()).powi(3)
+    }
+
+    fn assert_uniform(counts: &[u64], what: &str) {
+        let expected = counts.iter().sum::<u64>() as f64 / counts.len() as f64;
+        let chi2: f64 = counts
+            .iter()
+            .map(|&c| (c as f64 - expected).powi(2) / expected)
+            .sum();
+        let critical = chi_square_critical(counts.len() as u64 - 1);
+        assert!(chi2 < critical, "{what}: chi2 {chi2:.1} >= {critical:.1}");
+    }
+
+    fn check_small_bounds<R: BattleRng>(name: &str) {
+        let mut rng = R::seed_from_u64(0x5eed);
+        for end in [2u64, 3, 5, 7, 10, 64, 100, 1000] {
+            let mut counts = vec![0u64; end as usize];
+            for _ in 0..SAMPLES {
+                counts[rng.next(end) as usize] += 1;
+            }
+            assert_uniform(&counts, &format!("{name} next({end})"));
+        }
+    }
+
+    #[test]
+    fn next_is_uniform_over_small_bounds() {
+        check_small_bounds::<FastRng>("FastRng");
+        check_small_bounds::<WyRand>("WyRand");
+        check_small_bounds::<CounterRng>("CounterRng");
+    }
+
+    #[test]
+    fn next_inclusive_covers_both_ends() {
+        let mut rng = FastRng::seed_from_u64(1);
+        let mut counts = [0u64; 7];
+        for _ in 0..SAMPLES {
+            counts[rng.next_inclusive(6) as usize] += 1;
+        }
+        assert_uniform(&counts, "next_inclusive(6)");
+    }
+
+    #[test]
+    fn next_rejects_the_biased_zone() {
+        // end = 2^63 + 1 leaves a rejection zone of 2^63 - 1 low halves;
+        // 2 * end wraps to a low half of 2 and must be drawn again
+        let end = (1u64 << 63) + 1;
+        let mut rng = Scripted(vec![2, 1].into_iter());
+        assert_eq!(rng.next(end), 0);
+        assert!(rng.0.next().is_none());
+
+        let mut rng = Scripted(vec![u64::MAX].into_iter());
+        assert_eq!(rng.next(end), end - 1);
+    }
+
+    #[test]
+    fn next_is_uniform_just_above_2_pow_63() {
+        // plain `x % end` would put half of all draws below 2^62 instead of
+        // a third
+        let end = 3u64 << 62;
+        for seed in 0..3 {
+            let mut rng = WyRand::seed_from_u64(seed);
+            let mut thirds = [0u64; 3];
+            for _ in 0..SAMPLES {
+                let x = rng.next(end);
+                assert!(x < end);
+                thirds[(x >> 62) as usize] += 1;
+            }
+            assert_uniform(&thirds, "next(3 * 2^62)");
+        }
+    }
+}